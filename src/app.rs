@@ -1,20 +1,143 @@
-use crate::solver::{filter_useless_reagents, get_viable_start_reagents, priority_search};
-use std::{
-    error,
-    sync::{mpsc::channel, Arc},
-    thread,
-    time::Instant,
+use crate::sandbox::Sandbox;
+use crate::solver::{
+    finish_path, solve_with_progress, SolveReport, SolverOptions, StartProgress, Strategy,
 };
+use ratatui::layout::Rect;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::{Instant, SystemTime};
+use std::{error, fmt, fs, io};
 
-use crate::reagent::Reagent;
+use crate::clipboard::{copy_osc52, fallback_path, ClipboardMethod, CopyFormat};
+use crate::editor::ReagentEditor;
+use crate::export::{render_report, report_path, ExportFormat};
+use crate::files::{complete_path, display_path, FileBrowser};
+use crate::keymap::{Keymap, Mode};
+use crate::palette::Palette;
+use crate::reagent::{
+    load_reagents, parse_reagents, save_reagents, split_puzzle, validate_reagents, Reagent,
+    ReagentIssue, STDIN_FILE_NAME,
+};
+use crate::settings::{settings_path, Settings};
+use crate::theme::Theme;
+use crate::tree::TreeExplorer;
 
 /// Application result type.
 pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ActiveBlock {
     FileNameInput,
     ReagentOutput,
+    Settings,
+    Log,
+    SolverLog,
+}
+
+impl ActiveBlock {
+    /// Panes in focus order: down the left column, then the solver log.
+    const ORDER: [ActiveBlock; 5] = [
+        ActiveBlock::FileNameInput,
+        ActiveBlock::ReagentOutput,
+        ActiveBlock::Settings,
+        ActiveBlock::Log,
+        ActiveBlock::SolverLog,
+    ];
+
+    fn position(self) -> usize {
+        Self::ORDER.iter().position(|&b| b == self).unwrap_or(0)
+    }
+
+    pub fn next(self) -> Self {
+        Self::ORDER[(self.position() + 1) % Self::ORDER.len()]
+    }
+
+    pub fn previous(self) -> Self {
+        Self::ORDER[(self.position() + Self::ORDER.len() - 1) % Self::ORDER.len()]
+    }
+}
+
+/// Solver option shown on a line of the settings pane.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Setting {
+    Strategy,
+    MaxDepth,
+    MaxIterations,
+    BeamWidth,
+    RecordTree,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 5] = [
+        Setting::Strategy,
+        Setting::MaxDepth,
+        Setting::MaxIterations,
+        Setting::BeamWidth,
+        Setting::RecordTree,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Setting::Strategy => "Strategy",
+            Setting::MaxDepth => "Max depth",
+            Setting::MaxIterations => "Max iterations",
+            Setting::BeamWidth => "Beam width",
+            Setting::RecordTree => "Record tree",
+        }
+    }
+
+    /// Name of the setting in the command palette.
+    pub fn name(self) -> &'static str {
+        match self {
+            Setting::Strategy => "strategy",
+            Setting::MaxDepth => "max-depth",
+            Setting::MaxIterations => "max-iterations",
+            Setting::BeamWidth => "beam-width",
+            Setting::RecordTree => "record-tree",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.name() == name)
+    }
+
+    /// Sets the option from text, as typed in the palette or the settings file.
+    pub fn set(self, options: &mut SolverOptions, value: &str) -> Result<(), String> {
+        let number = || match value.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(format!("{} must be a positive number", self.name())),
+        };
+
+        match self {
+            Setting::Strategy => options.strategy = value.parse()?,
+            Setting::MaxDepth => options.max_depth = number()?,
+            Setting::MaxIterations => options.max_iterations = number()?,
+            Setting::BeamWidth => options.beam_width = number()?,
+            Setting::RecordTree => {
+                options.record_tree = match value {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(format!("{} must be on or off", self.name())),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn value(self, options: &SolverOptions) -> String {
+        match self {
+            Setting::Strategy => options.strategy.to_string(),
+            Setting::MaxDepth => options.max_depth.to_string(),
+            Setting::MaxIterations => options.max_iterations.to_string(),
+            Setting::BeamWidth => options.beam_width.to_string(),
+            Setting::RecordTree => match options.record_tree {
+                true => "on".to_string(),
+                false => "off".to_string(),
+            },
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -24,6 +147,259 @@ pub enum Status {
     Error,
 }
 
+/// Shown when a search tree is asked for but the last run kept none.
+const NO_SEARCH_TREE: &str =
+    "No search tree recorded, turn on record-tree and solve with the priority strategy";
+
+/// Terminals narrower than this get the stacked layout.
+pub const STACKED_MAX_WIDTH: u16 = 90;
+
+/// Terminals at least this wide get the details pane.
+pub const WIDE_MIN_WIDTH: u16 = 170;
+
+/// Arrangement of the panes, picked from the terminal size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LayoutMode {
+    /// Panes above the solver log, for narrow terminals.
+    Stacked,
+    /// Panes beside the solver log.
+    #[default]
+    Split,
+    /// Like split, with a details pane on the right.
+    Wide,
+}
+
+impl LayoutMode {
+    pub fn for_width(width: u16) -> Self {
+        if width < STACKED_MAX_WIDTH {
+            LayoutMode::Stacked
+        } else if width >= WIDE_MIN_WIDTH {
+            LayoutMode::Wide
+        } else {
+            LayoutMode::Split
+        }
+    }
+}
+
+/// Screen areas of the panes from the last render, used to route mouse events.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PaneAreas {
+    pub puzzle_tabs: Rect,
+    pub file_name_input: Rect,
+    /// Inside of the file name input, where the text is drawn.
+    pub file_name_text: Rect,
+    /// Inside of the reagent pane, where the reagent lines are drawn.
+    pub reagents: Rect,
+    pub settings: Rect,
+    pub log: Rect,
+    pub solver_log: Rect,
+    pub solver_tabs: Rect,
+    /// Inside of the solver log below the tabs.
+    pub solver_content: Rect,
+}
+
+/// What to do when the loaded puzzle file changes on disk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WatchMode {
+    #[default]
+    Off,
+    /// Re-read the puzzle.
+    Reload,
+    /// Re-read the puzzle and solve it again.
+    Solve,
+}
+
+impl WatchMode {
+    pub const ALL: [WatchMode; 3] = [WatchMode::Off, WatchMode::Reload, WatchMode::Solve];
+
+    pub fn next(self) -> Self {
+        match self {
+            WatchMode::Off => WatchMode::Reload,
+            WatchMode::Reload => WatchMode::Solve,
+            WatchMode::Solve => WatchMode::Off,
+        }
+    }
+}
+
+impl fmt::Display for WatchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchMode::Off => write!(f, "off"),
+            WatchMode::Reload => write!(f, "reload"),
+            WatchMode::Solve => write!(f, "solve"),
+        }
+    }
+}
+
+impl FromStr for WatchMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WatchMode::ALL
+            .into_iter()
+            .find(|mode| mode.to_string() == s.to_lowercase())
+            .ok_or_else(|| format!("unknown watch mode '{}' (expected off, reload or solve)", s))
+    }
+}
+
+/// Sent from the background search to the app.
+#[derive(Debug)]
+pub enum SearchMessage {
+    Progress(StartProgress),
+    Done(SolveReport),
+    /// Path finishing a sandbox prefix, `None` when there is none.
+    Finished(Option<Vec<String>>),
+}
+
+/// Solver run going on in the background.
+#[derive(Debug)]
+pub struct RunningSearch {
+    pub receiver: Receiver<SearchMessage>,
+    /// Latest progress per viable start, `None` until it first reports.
+    pub progress: Vec<Option<StartProgress>>,
+    pub options: SolverOptions,
+    pub started: Instant,
+    /// Sandbox path being finished, `None` for a full solver run.
+    pub finishing: Option<Vec<String>>,
+}
+
+/// An open puzzle with its own reagents and solver results, shown as a tab.
+#[derive(Debug)]
+pub struct Puzzle {
+    /// Parsed reagents
+    pub reagents: Vec<Reagent>,
+    /// exitus
+    pub exitus: Reagent,
+    /// reagents and exitus as editable text
+    pub reagent_editor: ReagentEditor,
+    /// problems found in the edited reagents
+    pub reagent_issues: Vec<ReagentIssue>,
+    /// file the puzzle was loaded from, `None` for pasted or piped puzzles
+    pub loaded_file: Option<String>,
+    /// modification time of the loaded file when it was last read or saved
+    pub loaded_mtime: Option<SystemTime>,
+    /// are there changes that have not been saved?
+    pub dirty: bool,
+    /// solver log
+    pub solver_log: Vec<String>,
+    /// result of the last solver run
+    pub solve_report: Option<SolveReport>,
+    /// solver run in progress, `None` when idle
+    pub search: Option<RunningSearch>,
+    /// selected solver log tab, 0 is the overview
+    pub solver_tab: usize,
+    /// first solver log line shown
+    pub solver_scroll: usize,
+    /// playground for applying reagents by hand, `None` when closed
+    pub sandbox: Option<Sandbox>,
+    /// index of the selected reagent
+    pub selected_reagent: usize,
+    /// first reagent pane line shown
+    pub reagent_scroll: usize,
+}
+
+impl Default for Puzzle {
+    fn default() -> Self {
+        Self {
+            reagents: Vec::new(),
+            exitus: Reagent {
+                name: "".to_string(),
+                atoms: Vec::new(),
+                score: None,
+            },
+            reagent_editor: ReagentEditor::default(),
+            reagent_issues: Vec::new(),
+            loaded_file: None,
+            loaded_mtime: None,
+            dirty: false,
+            solver_log: Vec::new(),
+            solve_report: None,
+            search: None,
+            solver_tab: 0,
+            solver_scroll: 0,
+            sandbox: None,
+            selected_reagent: 0,
+            reagent_scroll: 0,
+        }
+    }
+}
+
+impl Puzzle {
+    /// Nothing loaded, typed or pasted yet, so the tab can be reused.
+    pub fn is_empty(&self) -> bool {
+        self.loaded_file.is_none() && !self.dirty && self.exitus.name.is_empty()
+    }
+
+    /// Title of the puzzle tab: the file name, with `*` while modified and
+    /// `~` while solving.
+    pub fn title(&self) -> String {
+        let name = match (&self.loaded_file, self.is_empty()) {
+            (Some(file_name), _) => Path::new(file_name)
+                .file_name()
+                .map_or(file_name.clone(), |n| n.to_string_lossy().into_owned()),
+            (None, true) => "new".to_string(),
+            (None, false) => "untitled".to_string(),
+        };
+        let dirty = if self.dirty { "*" } else { "" };
+        let solving = if self.search.is_some() { "~" } else { "" };
+        format!("{}{}{}", name, dirty, solving)
+    }
+
+    /// Forgets the last solver run and stops listening to a running one, so
+    /// results for other contents never show up on this puzzle. An open
+    /// sandbox starts over, its path may use reagents that are gone.
+    pub fn clear_solver(&mut self) {
+        self.search = None;
+        self.solve_report = None;
+        self.solver_log = Vec::new();
+        self.solver_tab = 0;
+        self.solver_scroll = 0;
+        if self.sandbox.is_some() {
+            self.sandbox = Some(Sandbox::new());
+        }
+    }
+
+    /// Takes in what the background search sent since the last tick.
+    pub fn poll_search(&mut self) {
+        let Some(search) = &mut self.search else {
+            return;
+        };
+
+        let mut report = None;
+        for message in search.receiver.try_iter() {
+            match message {
+                SearchMessage::Progress(progress) => {
+                    let index = progress.index;
+                    if search.progress.len() <= index {
+                        search.progress.resize_with(index + 1, || None);
+                    }
+                    search.progress[index] = Some(progress);
+                }
+                SearchMessage::Done(done) => report = Some(done),
+                SearchMessage::Finished(path) => {
+                    let prefix = search.finishing.take();
+                    self.search = None;
+                    // The sandbox may have moved on or closed meanwhile.
+                    if let Some(sandbox) = &mut self.sandbox {
+                        if prefix == Some(sandbox.current().reagent_path) {
+                            sandbox.finished = Some(path);
+                        }
+                    }
+                    return;
+                }
+            }
+        }
+
+        if let Some(report) = report {
+            self.search = None;
+            self.solver_log = report.log_lines();
+            self.solve_report = Some(report);
+            self.solver_tab = 0;
+            self.solver_scroll = 0;
+        }
+    }
+}
+
 /// Application.
 #[derive(Debug)]
 pub struct App {
@@ -31,24 +407,60 @@ pub struct App {
     pub running: bool,
     /// Reagent file name
     pub file_name_input: String,
-    /// Parsed reagents
-    pub reagents: Vec<Reagent>,
     /// Edit mode
     pub edit_mode: bool,
     /// active block
     pub active_block: ActiveBlock,
-    /// exitus
-    pub exitus: Reagent,
     /// log message
     pub log_message: String,
     /// status
     pub status: Status,
-    /// solver log
-    pub solver_log: Vec<String>,
     /// char index
     pub character_index: usize,
-    /// reagents and exitus
-    pub reagents_and_exitus: String,
+    /// first file name character shown, so the cursor stays in view
+    pub file_name_scroll: usize,
+    /// solver strategy and limits
+    pub solver_options: SolverOptions,
+    /// puzzle read from standard input, kept since it can only be read once
+    pub piped_input: Option<String>,
+    /// what happens when the loaded file changes on disk
+    pub watch: WatchMode,
+    /// colours of the interface
+    pub theme: Theme,
+    /// how copied solutions are written
+    pub copy_format: CopyFormat,
+    /// where copied solutions go
+    pub clipboard_method: ClipboardMethod,
+    /// session and preferences, written back on exit
+    pub settings: Settings,
+    /// where the settings are written, `None` to not write them
+    pub settings_path: Option<PathBuf>,
+    /// is the quit confirmation shown?
+    pub confirm_quit: bool,
+    /// is the confirmation for reloading over unsaved changes shown?
+    pub confirm_load: bool,
+    /// open puzzles, one per tab
+    pub puzzles: Vec<Puzzle>,
+    /// index of the puzzle shown
+    pub active_puzzle: usize,
+    /// index into [`Setting::ALL`] of the selected setting
+    pub selected_setting: usize,
+    /// pane areas from the last render
+    pub areas: PaneAreas,
+    /// arrangement of the panes for the terminal size
+    pub layout: LayoutMode,
+    /// key bindings
+    pub keymap: Keymap,
+    /// mode whose keys the help overlay lists, `None` when closed
+    pub help: Option<Mode>,
+    /// command being typed, `None` when the palette is closed
+    pub palette: Option<Palette>,
+    /// popup for picking a puzzle file, `None` when closed
+    pub file_browser: Option<FileBrowser>,
+    /// selected line of the recent files popup, `None` when closed
+    pub recent: Option<usize>,
+    /// popup exploring a recorded search tree, `None` when closed
+    pub search_tree: Option<TreeExplorer>,
 }
 
 impl Default for App {
@@ -58,19 +470,33 @@ impl Default for App {
         Self {
             running: true,
             file_name_input: file_name_input.clone(),
-            reagents: Vec::new(),
             log_message: String::new(),
-            exitus: Reagent {
-                name: "".to_string(),
-                atoms: Vec::new(),
-                score: None,
-            },
-            reagents_and_exitus: String::new(),
             active_block: ActiveBlock::FileNameInput,
             status: Status::Neutral,
             edit_mode: false,
-            solver_log: Vec::new(),
             character_index: file_name_input.len(),
+            file_name_scroll: 0,
+            solver_options: SolverOptions::default(),
+            piped_input: None,
+            watch: WatchMode::default(),
+            theme: Theme::default(),
+            copy_format: CopyFormat::default(),
+            clipboard_method: ClipboardMethod::default(),
+            settings: Settings::default(),
+            settings_path: None,
+            confirm_quit: false,
+            confirm_load: false,
+            puzzles: vec![Puzzle::default()],
+            active_puzzle: 0,
+            selected_setting: 0,
+            areas: PaneAreas::default(),
+            layout: LayoutMode::default(),
+            keymap: Keymap::default(),
+            help: None,
+            palette: None,
+            file_browser: None,
+            recent: None,
+            search_tree: None,
         }
     }
 }
@@ -82,17 +508,267 @@ impl App {
     }
 
     /// Handles the tick event of the terminal.
-    pub fn tick(&self) {}
+    pub fn tick(&mut self) {
+        self.poll_search();
+        self.poll_watched_file();
+    }
+
+    /// Picks the layout for a new terminal size.
+    ///
+    /// The pane areas are those of the old size until the next render, so
+    /// they are dropped rather than used to route mouse events.
+    pub fn resize(&mut self, width: u16, _height: u16) {
+        self.layout = LayoutMode::for_width(width);
+        self.areas = PaneAreas::default();
+    }
 
     /// Set running to false to quit the application.
     pub fn quit(&mut self) {
         self.running = false;
     }
 
+    /// Quits, or asks for confirmation first when a puzzle has unsaved
+    /// changes.
+    pub fn request_quit(&mut self) {
+        match self.puzzles.iter().any(|p| p.dirty) {
+            true => self.confirm_quit = true,
+            false => self.quit(),
+        }
+    }
+
+    /// The puzzle shown.
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzles[self.active_puzzle]
+    }
+
+    pub fn puzzle_mut(&mut self) -> &mut Puzzle {
+        &mut self.puzzles[self.active_puzzle]
+    }
+
+    /// Shows another puzzle, putting its file in the file name input.
+    pub fn select_puzzle(&mut self, index: usize) {
+        if index >= self.puzzles.len() {
+            return;
+        }
+        self.active_puzzle = index;
+        self.edit_mode = false;
+        if let Some(file_name) = self.puzzle().loaded_file.clone() {
+            self.file_name_input = file_name;
+            self.character_index = self.file_name_input.chars().count();
+        }
+    }
+
+    pub fn next_puzzle(&mut self) {
+        self.select_puzzle((self.active_puzzle + 1) % self.puzzles.len());
+    }
+
+    pub fn previous_puzzle(&mut self) {
+        let puzzles = self.puzzles.len();
+        self.select_puzzle((self.active_puzzle + puzzles - 1) % puzzles);
+    }
+
+    /// Opens an empty puzzle tab.
+    pub fn new_puzzle(&mut self) {
+        self.puzzles.push(Puzzle::default());
+        self.select_puzzle(self.puzzles.len() - 1);
+    }
+
+    /// Closes the puzzle shown, unless it has unsaved changes. Closing the
+    /// last one leaves an empty tab.
+    pub fn close_puzzle(&mut self) {
+        if self.puzzle().dirty {
+            self.status = Status::Error;
+            self.log_message = format!(
+                "{} has unsaved changes, save or reload it first",
+                self.puzzle().title()
+            );
+            return;
+        }
+
+        self.puzzles.remove(self.active_puzzle);
+        if self.puzzles.is_empty() {
+            self.puzzles.push(Puzzle::default());
+        }
+        self.select_puzzle(self.active_puzzle.min(self.puzzles.len() - 1));
+    }
+
+    /// Puzzle tab whose title is drawn at column `column`.
+    pub fn puzzle_tab_at_column(&self, column: u16) -> Option<usize> {
+        let titles: Vec<String> = self.puzzles.iter().map(Puzzle::title).collect();
+        tab_at_column(&titles, self.areas.puzzle_tabs.x, column)
+    }
+
+    /// Saves every modified puzzle, e.g. before quitting.
+    pub fn save_all(&mut self) {
+        let active = self.active_puzzle;
+        for index in 0..self.puzzles.len() {
+            // Puzzles without a file only go to the typed name when shown.
+            let puzzle = &self.puzzles[index];
+            if puzzle.dirty && (index == active || puzzle.loaded_file.is_some()) {
+                self.active_puzzle = index;
+                self.save();
+                // Keep the error of the first puzzle that could not be saved.
+                if self.puzzles[index].dirty {
+                    break;
+                }
+            }
+        }
+        self.active_puzzle = active;
+    }
+
+    /// Replaces the default key bindings with the user's keymap file.
+    pub fn load_keymap(&mut self) {
+        let (keymap, error) = Keymap::load();
+        self.keymap = keymap;
+        if let Some(error) = error {
+            self.status = Status::Error;
+            self.log_message = format!("Error loading keymap: {}", error);
+        }
+    }
+
+    /// Reads the settings file at `path`, or the default one, and applies
+    /// the solver options, watch mode and key bindings in it.
+    ///
+    /// A broken file is reported and left alone: the session is not written
+    /// back over it on exit.
+    pub fn load_settings(&mut self, path: Option<PathBuf>) {
+        let Some(path) = path.or_else(settings_path) else {
+            return;
+        };
+
+        let settings = match Settings::load(&path) {
+            Ok(settings) => settings,
+            Err(e) => {
+                self.status = Status::Error;
+                self.log_message = format!(
+                    "Error loading settings {}: {} (not saved this session)",
+                    path.display(),
+                    e
+                );
+                return;
+            }
+        };
+
+        let mut keymap = self.keymap.clone();
+        match keymap.apply_config(&settings.keys) {
+            Ok(_) => self.keymap = keymap,
+            Err(e) => {
+                self.status = Status::Error;
+                self.log_message = format!("Error in the keys of {}: {}", path.display(), e);
+            }
+        }
+
+        self.solver_options = settings.solver_options;
+        self.watch = settings.watch;
+        self.theme = settings.theme;
+        self.copy_format = settings.copy_format;
+        self.clipboard_method = settings.clipboard_method;
+        self.settings = settings;
+        self.settings_path = Some(path);
+    }
+
+    /// Writes the session and preferences to the settings file.
+    pub fn save_settings(&mut self) -> io::Result<()> {
+        let Some(path) = &self.settings_path else {
+            return Ok(());
+        };
+
+        self.settings.solver_options = self.solver_options;
+        self.settings.watch = self.watch;
+        self.settings.theme = self.theme;
+        self.settings.copy_format = self.copy_format;
+        self.settings.clipboard_method = self.clipboard_method;
+        self.settings.open_files = self
+            .puzzles
+            .iter()
+            .filter_map(|puzzle| puzzle.loaded_file.as_deref())
+            .map(|file_name| absolute_path(file_name).to_string_lossy().into_owned())
+            .collect();
+        self.settings.save(path)
+    }
+
+    /// Reopens the puzzles of the last session that still exist, or else
+    /// the most recent file.
+    pub fn restore_session(&mut self) {
+        let open_files: Vec<String> = self
+            .settings
+            .open_files
+            .iter()
+            .filter(|file_name| Path::new(file_name).is_file())
+            .map(|file_name| display_path(Path::new(file_name)))
+            .collect();
+
+        if !open_files.is_empty() {
+            for file_name in &open_files {
+                self.open_file(file_name);
+            }
+            self.select_puzzle(0);
+            return;
+        }
+
+        let Some(file_name) = self.settings.recent_files.first() else {
+            return;
+        };
+        let file_name = display_path(Path::new(file_name));
+        match Path::new(&file_name).is_file() {
+            true => self.open_file(&file_name),
+            false => {
+                self.file_name_input = file_name;
+                self.character_index = self.file_name_input.chars().count();
+            }
+        }
+    }
+
+    /// Puts a file at the front of the recent files, by its absolute path so
+    /// it is found again from another directory.
+    fn remember_file(&mut self, file_name: &str) {
+        let path = absolute_path(file_name);
+        self.settings.add_recent_file(&path.to_string_lossy());
+    }
+
+    /// Input mode deciding which key bindings apply.
+    pub fn mode(&self) -> Mode {
+        if self.confirm_quit {
+            Mode::ConfirmQuit
+        } else if self.confirm_load {
+            Mode::ConfirmLoad
+        } else if self.palette.is_some() {
+            Mode::Palette
+        } else if self.file_browser.is_some() {
+            Mode::Browser
+        } else if self.recent.is_some() {
+            Mode::Recent
+        } else if self.search_tree.is_some() {
+            Mode::Tree
+        } else if self.edit_mode && self.active_block == ActiveBlock::ReagentOutput {
+            Mode::Editor
+        } else if self.edit_mode {
+            Mode::Input
+        } else if self.puzzle().sandbox.is_some() {
+            Mode::Sandbox
+        } else {
+            Mode::Normal
+        }
+    }
+
     pub fn switch_active_block(&mut self, active_block: ActiveBlock) {
         self.active_block = active_block;
     }
 
+    /// Moves focus to a pane, leaving edit mode.
+    pub fn focus(&mut self, active_block: ActiveBlock) {
+        self.edit_mode = false;
+        self.switch_active_block(active_block);
+    }
+
+    pub fn focus_next(&mut self) {
+        self.focus(self.active_block.next());
+    }
+
+    pub fn focus_previous(&mut self) {
+        self.focus(self.active_block.previous());
+    }
+
     pub fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
         new_cursor_pos.clamp(0, self.file_name_input.chars().count())
     }
@@ -122,6 +798,13 @@ impl App {
         self.move_cursor_right();
     }
 
+    /// Inserts `text` at the cursor, dropping control characters.
+    pub fn enter_str(&mut self, text: &str) {
+        for c in text.chars().filter(|c| !c.is_control()) {
+            self.enter_char(c);
+        }
+    }
+
     pub fn delete_char(&mut self) {
         let is_not_cursor_leftmost = self.character_index != 0;
         if is_not_cursor_leftmost {
@@ -147,96 +830,866 @@ impl App {
         }
     }
 
-    pub fn find_solutions(&mut self) {
-        self.solver_log.clear();
-        self.solver_log
-            .push("Removing useless reagents...".to_string());
-
-        let (filtered_reagents, useless_reagents) =
-            filter_useless_reagents(&self.exitus, &self.reagents);
-
-        match useless_reagents.len() {
-            0 => {
-                self.solver_log
-                    .push(" \u{21B3}No useless reagents found\n".to_string());
-            }
-            _ => {
-                self.solver_log.push(format!(
-                    " \u{21B3}Removed {}\n",
-                    useless_reagents
-                        .iter()
-                        .map(|r| r.name.clone())
-                        .collect::<Vec<String>>()
-                        .join(", ")
-                ));
-            }
-        }
-
-        self.solver_log
-            .push("Looking for viable start reagents...".to_string());
-        let viable_starts = get_viable_start_reagents(&self.exitus, &filtered_reagents);
-
-        match viable_starts.len() {
-            0 => {
-                self.solver_log
-                    .push(" \u{21B3}No viable start reagents found\n".to_string());
+    /// Loads the typed file into the puzzle shown, or asks for confirmation
+    /// first when that would throw away unsaved changes.
+    pub fn request_load(&mut self) {
+        match self.puzzle().dirty {
+            true => self.confirm_load = true,
+            false => self.load_reagents_file(),
+        }
+    }
+
+    /// Reads and parses the file named in the file name input.
+    ///
+    /// Standard input is only read the first time, reloading it re-parses
+    /// what was piped in.
+    pub fn load_reagents_file(&mut self) {
+        let contents = match (self.file_name_input.as_str(), &self.piped_input) {
+            (STDIN_FILE_NAME, Some(contents)) => Ok(contents.clone()),
+            (file_name, _) => load_reagents(file_name),
+        };
+
+        let contents = match contents {
+            Ok(contents) => contents,
+            Err(e) => {
+                self.status = Status::Error;
+                self.log_message = format!("Error loading reagents: {}", e);
+                return;
+            }
+        };
+
+        if self.file_name_input == STDIN_FILE_NAME {
+            self.piped_input = Some(contents.clone());
+        }
+
+        let file_name = self.file_name_input.clone();
+        self.load_contents(&file_name, &contents);
+
+        if let Status::Ok = self.status {
+            let puzzle = self.puzzle_mut();
+            puzzle.loaded_file = match file_name.as_str() {
+                STDIN_FILE_NAME => None,
+                _ => Some(file_name),
+            };
+            puzzle.loaded_mtime = puzzle.loaded_file.as_deref().and_then(file_mtime);
+            puzzle.dirty = false;
+            if let Some(file_name) = puzzle.loaded_file.clone() {
+                self.remember_file(&file_name);
+            }
+        }
+    }
+
+    /// Re-reads the file of the puzzle shown when watching and it changed
+    /// on disk. Other puzzles catch up once they are shown again.
+    ///
+    /// A file that does not parse cleanly, e.g. one saved halfway through an
+    /// edit, is reported and the last good puzzle is kept. Unsaved changes
+    /// in the app are never thrown away.
+    pub fn poll_watched_file(&mut self) {
+        if self.watch == WatchMode::Off {
+            return;
+        }
+        let Some(file_name) = self.puzzle().loaded_file.clone() else {
+            return;
+        };
+        let mtime = file_mtime(&file_name);
+        if mtime.is_none() || mtime == self.puzzle().loaded_mtime {
+            return;
+        }
+        // Only try once per change, the next save will be picked up again.
+        self.puzzle_mut().loaded_mtime = mtime;
+
+        if self.puzzle().dirty {
+            self.status = Status::Error;
+            self.log_message = format!(
+                "{} changed on disk, keeping unsaved changes (r - reload)",
+                file_name
+            );
+            return;
+        }
+
+        let contents = match load_reagents(&file_name) {
+            Ok(contents) => contents,
+            Err(e) => {
+                self.status = Status::Error;
+                self.log_message = format!("Error reloading {}: {}", file_name, e);
                 return;
             }
-            _ => {
-                self.solver_log.push(format!(
-                    " \u{21B3}Found {} \n",
-                    viable_starts
-                        .iter()
-                        .map(|r| r.name.clone())
-                        .collect::<Vec<String>>()
-                        .join(", ")
-                ));
+        };
+        if let Some(issue) = validate_reagents(&contents).first() {
+            self.status = Status::Error;
+            self.log_message = format!("Not reloaded, {}: {}", file_name, issue);
+            return;
+        }
+
+        self.load_contents(&file_name, &contents);
+        if let Status::Ok = self.status {
+            self.log_message = format!("Reloaded {}", file_name);
+            if self.watch == WatchMode::Solve
+                && !self.puzzle().reagents.is_empty()
+                && self.puzzle().search.is_none()
+            {
+                self.find_solutions();
             }
         }
+    }
 
-        let exitus = Arc::new(self.exitus.clone());
-        let reagents = Arc::new(self.reagents.clone());
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+        self.status = Status::Ok;
+        self.log_message = format!("Theme {}", theme);
+    }
 
-        let (sender, receiver) = channel();
+    /// Switches to the next watch mode.
+    pub fn cycle_watch(&mut self) {
+        self.set_watch(self.watch.next());
+    }
 
-        self.solver_log.push("Searching...\n".to_string());
-
-        for start in viable_starts {
-            let start = start.clone();
-            let sender = sender.clone();
-            let exitus = Arc::clone(&exitus);
-            let reagents = Arc::clone(&reagents);
-
-            thread::spawn(move || {
-                let start_time = Instant::now();
-                let path = priority_search(&exitus, &start, &reagents);
-                let elapsed = start_time.elapsed();
-                match sender.send((start, path, elapsed)) {
-                    Ok(_) => {}
-                    Err(e) => {
-                        eprintln!("Error sending path: {:?}\n", e);
-                    }
+    pub fn set_watch(&mut self, watch: WatchMode) {
+        self.watch = watch;
+        // Start from the files as they are now rather than reloading right away.
+        for puzzle in &mut self.puzzles {
+            puzzle.loaded_mtime = puzzle.loaded_file.as_deref().and_then(file_mtime);
+        }
+        self.status = Status::Ok;
+        self.log_message = match (watch, &self.puzzle().loaded_file) {
+            (WatchMode::Off, _) => "Stopped watching".to_string(),
+            (_, Some(file_name)) => format!("Watching {} ({})", file_name, watch),
+            (_, None) => format!("Watch mode {}, load a file to watch it", watch),
+        };
+    }
+
+    /// Parses a pasted reagent list, replacing the loaded puzzle. A puzzle
+    /// with unsaved changes is kept and the paste goes into a new tab.
+    pub fn load_pasted_reagents(&mut self, contents: &str) {
+        let previous = self.active_puzzle;
+        if self.puzzle().dirty {
+            self.new_puzzle();
+        }
+
+        self.load_contents("pasted text", contents);
+        if let Status::Ok = self.status {
+            let puzzle = &mut self.puzzles[self.active_puzzle];
+            self.log_message = format!("Pasted {} reagents", puzzle.reagents.len());
+            puzzle.loaded_file = None;
+            puzzle.loaded_mtime = None;
+            puzzle.dirty = true;
+        } else if self.active_puzzle != previous {
+            // Do not leave an empty tab behind a paste that did not parse.
+            self.puzzles.remove(self.active_puzzle);
+            self.active_puzzle = previous;
+        }
+    }
+
+    /// Parses puzzle text, keeping the current puzzle when there is no exitus.
+    fn load_contents(&mut self, source: &str, contents: &str) {
+        match split_puzzle(source, contents) {
+            Ok((exitus, reagents)) => {
+                let puzzle = &mut self.puzzles[self.active_puzzle];
+                puzzle.exitus = exitus;
+                puzzle.reagents = reagents;
+                puzzle.reagent_editor = ReagentEditor::new(contents);
+                puzzle.reagent_issues = validate_reagents(contents);
+                puzzle.clear_solver();
+                self.search_tree = None;
+                self.status = Status::Ok;
+                self.log_message = format!("Loaded {} reagents", puzzle.reagents.len());
+            }
+            Err(e) => {
+                self.status = Status::Error;
+                self.log_message = e;
+            }
+        }
+    }
+
+    /// Re-parses the reagent editor after an edit.
+    ///
+    /// The puzzle is only replaced when the text is free of issues, so a
+    /// half-typed reagent never throws away the last working puzzle. Once it
+    /// is replaced, the solver results for the old one are dropped.
+    pub fn reparse_editor(&mut self) {
+        let puzzle = &mut self.puzzles[self.active_puzzle];
+        let contents = puzzle.reagent_editor.text();
+        puzzle.reagent_issues = validate_reagents(&contents);
+        puzzle.dirty = true;
+
+        match puzzle.reagent_issues.first() {
+            None => {
+                if let (Some(exitus), reagents) = parse_reagents(&contents) {
+                    puzzle.exitus = exitus;
+                    puzzle.reagents = reagents;
+                    puzzle.clear_solver();
+                    self.search_tree = None;
                 }
-            });
+                self.status = Status::Ok;
+                self.log_message = format!("{} reagents", puzzle.reagents.len());
+            }
+            Some(issue) => {
+                self.status = Status::Error;
+                self.log_message = match puzzle.reagent_issues.len() {
+                    1 => issue.to_string(),
+                    n => format!("{} (+{} more)", issue, n - 1),
+                };
+            }
+        }
+    }
+
+    /// Saves the puzzle to the file it was loaded from.
+    ///
+    /// Pasted and piped puzzles have no file yet, so they are saved to the
+    /// name in the file name input instead.
+    pub fn save(&mut self) {
+        let file_name = self
+            .puzzle()
+            .loaded_file
+            .clone()
+            .unwrap_or_else(|| self.file_name_input.clone());
+        self.save_to(&file_name);
+    }
+
+    /// Saves the puzzle to the name in the file name input.
+    pub fn save_as(&mut self) {
+        let file_name = self.file_name_input.clone();
+        self.save_to(&file_name);
+    }
+
+    fn save_to(&mut self, file_name: &str) {
+        if file_name.is_empty() || file_name == STDIN_FILE_NAME {
+            self.status = Status::Error;
+            self.log_message = "Enter a file name to save to".to_string();
+            return;
+        }
+        if self.puzzle().exitus.name.is_empty() {
+            self.status = Status::Error;
+            self.log_message = "Nothing to save".to_string();
+            return;
+        }
+        // The last good puzzle would replace the lines that do not parse.
+        if let Some(issue) = self.puzzle().reagent_issues.first() {
+            self.log_message = format!("Fix the reagents before saving: {}", issue);
+            self.status = Status::Error;
+            return;
+        }
+
+        let puzzle = self.puzzle_mut();
+        let template = puzzle.reagent_editor.text();
+        match save_reagents(file_name, &template, &puzzle.exitus, &puzzle.reagents) {
+            Ok(_) => {
+                puzzle.loaded_file = Some(file_name.to_string());
+                puzzle.loaded_mtime = file_mtime(file_name);
+                puzzle.dirty = false;
+                self.remember_file(file_name);
+                self.status = Status::Ok;
+                self.log_message = format!("Saved {}", file_name);
+            }
+            Err(e) => {
+                self.status = Status::Error;
+                self.log_message = format!("Error saving {}: {}", file_name, e);
+            }
+        }
+    }
+
+    /// Shows the puzzle of `file_name`, loading it in a new tab unless it is
+    /// open already. An empty tab is reused rather than left behind.
+    pub fn open_file(&mut self, file_name: &str) {
+        let path = Path::new(file_name).canonicalize().ok();
+        let open = self.puzzles.iter().position(|puzzle| {
+            let loaded = puzzle.loaded_file.as_deref().map(Path::new);
+            path.is_some() && loaded.and_then(|p| p.canonicalize().ok()) == path
+        });
+        if let Some(index) = open {
+            self.select_puzzle(index);
+            return;
+        }
+
+        let previous = self.active_puzzle;
+        if !self.puzzle().is_empty() {
+            self.new_puzzle();
         }
 
-        drop(sender);
+        self.file_name_input = file_name.to_string();
+        self.character_index = self.file_name_input.chars().count();
+        self.load_reagents_file();
 
-        for (start, path_option, duration) in receiver {
-            match path_option {
-                Some(path) => {
-                    self.solver_log.push(format!(
-                        "Path for start {} \n \u{21B3}{} \n \u{21B3}found in {} microseconds\n",
-                        start.name,
-                        path.join(" -> "),
-                        duration.as_micros(),
-                    ));
+        // Do not leave an empty tab behind a file that could not be read.
+        if self.puzzle().is_empty() && self.active_puzzle != previous {
+            self.puzzles.remove(self.active_puzzle);
+            self.active_puzzle = previous;
+        }
+    }
+
+    /// Completes the path in the file name input, listing the matches in the
+    /// log when there is more than one.
+    pub fn complete_file_name(&mut self) {
+        let (completed, candidates) = complete_path(&self.file_name_input);
+        self.file_name_input = completed;
+        self.character_index = self.file_name_input.chars().count();
+
+        if !candidates.is_empty() {
+            self.status = Status::Neutral;
+            self.log_message = candidates.join("  ");
+        }
+    }
+
+    /// Opens the file browser in the directory of the file name input,
+    /// falling back to the working directory.
+    pub fn open_file_browser(&mut self) {
+        let typed_dir = Path::new(&self.file_name_input)
+            .parent()
+            .filter(|dir| dir.is_dir())
+            .unwrap_or(Path::new("."))
+            .to_path_buf();
+
+        match FileBrowser::new(&typed_dir).or_else(|_| FileBrowser::new(Path::new("."))) {
+            Ok(browser) => self.file_browser = Some(browser),
+            Err(e) => {
+                self.status = Status::Error;
+                self.log_message = format!("Error listing files: {}", e);
+            }
+        }
+    }
+
+    /// Opens the selected directory, or loads the selected puzzle and closes
+    /// the browser.
+    pub fn browser_open_selected(&mut self) {
+        let Some(browser) = &mut self.file_browser else {
+            return;
+        };
+        let (Some(entry), Some(path)) = (browser.selected_entry(), browser.selected_path()) else {
+            return;
+        };
+
+        if entry.is_dir {
+            if let Err(e) = browser.change_dir(&path) {
+                self.status = Status::Error;
+                self.log_message = format!("Error listing {}: {}", path.display(), e);
+            }
+        } else {
+            self.file_browser = None;
+            self.open_file(&display_path(&path));
+        }
+    }
+
+    /// Opens the recent files popup.
+    pub fn open_recent(&mut self) {
+        match self.settings.recent_files.is_empty() {
+            true => {
+                self.status = Status::Error;
+                self.log_message = "No recent files yet".to_string();
+            }
+            false => self.recent = Some(0),
+        }
+    }
+
+    pub fn select_recent(&mut self, index: usize) {
+        let last = self.settings.recent_files.len().saturating_sub(1);
+        self.recent = Some(index.min(last));
+    }
+
+    /// Opens the recent file selected in the popup and closes it.
+    pub fn recent_open_selected(&mut self) {
+        let Some(file_name) = self
+            .recent
+            .take()
+            .and_then(|index| self.settings.recent_files.get(index))
+        else {
+            return;
+        };
+
+        let file_name = display_path(Path::new(file_name));
+        self.open_file(&file_name);
+    }
+
+    /// Opens the search tree explorer on the start of the solution tab
+    /// shown, or on the first start with a recorded tree.
+    pub fn open_search_tree(&mut self) {
+        let puzzle = self.puzzle();
+        let Some(report) = &puzzle.solve_report else {
+            self.status = Status::Error;
+            self.log_message = "Solve the puzzle before exploring the search tree".to_string();
+            return;
+        };
+
+        let shown = puzzle.solver_tab.checked_sub(1).and_then(|tab| {
+            (0..report.results.len())
+                .filter(|&i| report.results[i].path.is_some())
+                .nth(tab)
+        });
+        let recorded = |i: &usize| report.results[*i].tree.is_some();
+
+        match shown
+            .filter(recorded)
+            .or_else(|| (0..report.results.len()).find(recorded))
+        {
+            Some(index) => self.show_search_tree(index),
+            None => {
+                self.status = Status::Error;
+                self.log_message = NO_SEARCH_TREE.to_string();
+            }
+        }
+    }
+
+    fn show_search_tree(&mut self, index: usize) {
+        let puzzle = self.puzzle();
+        let Some(report) = &puzzle.solve_report else {
+            return;
+        };
+        let result = &report.results[index];
+        let Some(tree) = &result.tree else {
+            return;
+        };
+
+        self.search_tree = Some(TreeExplorer::new(
+            index,
+            &result.start.name,
+            tree.clone(),
+            &report.exitus.atoms,
+        ));
+    }
+
+    /// Shows the tree of the next or previous start that recorded one.
+    pub fn search_tree_cycle_start(&mut self, forward: bool) {
+        let (Some(explorer), Some(report)) = (&self.search_tree, &self.puzzle().solve_report)
+        else {
+            return;
+        };
+
+        let recorded: Vec<usize> = (0..report.results.len())
+            .filter(|&i| report.results[i].tree.is_some())
+            .collect();
+        let Some(position) = recorded.iter().position(|&i| i == explorer.result) else {
+            return;
+        };
+
+        let next = match forward {
+            true => (position + 1) % recorded.len(),
+            false => (position + recorded.len() - 1) % recorded.len(),
+        };
+        self.show_search_tree(recorded[next]);
+    }
+
+    /// Selects where the branch of the selected node left the exitus.
+    pub fn search_tree_jump_to_divergence(&mut self) {
+        let Some(explorer) = &mut self.search_tree else {
+            return;
+        };
+
+        match explorer.jump_to_divergence() {
+            Some(node) => {
+                self.status = Status::Neutral;
+                self.log_message = format!(
+                    "Branch left the exitus at {}, depth {}: {} of {} atoms in place",
+                    explorer.node(node).reagent,
+                    explorer.node(node).depth,
+                    explorer.matched(node),
+                    explorer.exitus.len()
+                );
+            }
+            None => {
+                self.status = Status::Ok;
+                self.log_message = "Branch never lost an exitus atom it had in place".to_string();
+            }
+        }
+    }
+
+    /// Path of the solution tab shown, or the shortest one from the
+    /// overview.
+    pub fn selected_solution(&self) -> Option<&Vec<String>> {
+        let report = self.puzzle().solve_report.as_ref()?;
+        match self.puzzle().solver_tab.checked_sub(1) {
+            Some(i) => report.solutions().nth(i)?.path.as_ref(),
+            None => report.best_path(),
+        }
+    }
+
+    /// Copies the selected solution to the clipboard, or writes it next to
+    /// the puzzle when the terminal cannot be written to or the clipboard
+    /// method is `file`.
+    pub fn copy_solution(&mut self, format: Option<CopyFormat>) {
+        let Some(path) = self.selected_solution() else {
+            self.status = Status::Error;
+            self.log_message = "No solution to copy, solve the puzzle first".to_string();
+            return;
+        };
+        let text = format.unwrap_or(self.copy_format).format(path);
+        let steps = path.len();
+
+        let clipboard_error = match self.clipboard_method {
+            ClipboardMethod::Osc52 => match copy_osc52(&text) {
+                Ok(_) => {
+                    self.status = Status::Ok;
+                    self.log_message = format!("Copied a {} reagent path", steps);
+                    return;
                 }
-                None => {
-                    self.solver_log
-                        .push(format!("No path found for start {}", start.name));
+                Err(e) => Some(e),
+            },
+            ClipboardMethod::File => None,
+        };
+
+        let file_name = fallback_path(self.puzzle().loaded_file.as_deref());
+        match (fs::write(&file_name, text + "\n"), clipboard_error) {
+            (Ok(_), None) => {
+                self.status = Status::Ok;
+                self.log_message = format!("Wrote the path to {}", file_name);
+            }
+            (Ok(_), Some(e)) => {
+                self.status = Status::Error;
+                self.log_message = format!("Cannot copy ({}), wrote the path to {}", e, file_name);
+            }
+            (Err(e), _) => {
+                self.status = Status::Error;
+                self.log_message = format!("Error writing {}: {}", file_name, e);
+            }
+        }
+    }
+
+    /// Writes the last solver run to `file_name`, or next to the puzzle.
+    pub fn export_report(&mut self, format: ExportFormat, file_name: Option<String>) {
+        let puzzle = &self.puzzles[self.active_puzzle];
+        let Some(report) = &puzzle.solve_report else {
+            self.status = Status::Error;
+            self.log_message = "Solve the puzzle before exporting".to_string();
+            return;
+        };
+        if format == ExportFormat::Dot && report.results.iter().all(|r| r.tree.is_none()) {
+            self.status = Status::Error;
+            self.log_message = NO_SEARCH_TREE.to_string();
+            return;
+        }
+
+        let file_name =
+            file_name.unwrap_or_else(|| report_path(puzzle.loaded_file.as_deref(), format));
+        let source = puzzle.loaded_file.as_deref().unwrap_or(STDIN_FILE_NAME);
+        let contents = render_report(format, source, report);
+
+        match fs::write(&file_name, contents) {
+            Ok(_) => {
+                self.status = Status::Ok;
+                self.log_message = format!("Exported {}", file_name);
+            }
+            Err(e) => {
+                self.status = Status::Error;
+                self.log_message = format!("Error exporting {}: {}", file_name, e);
+            }
+        }
+    }
+
+    /// Starts solving on a background thread; [`App::tick`] picks up the
+    /// progress and the report.
+    pub fn find_solutions(&mut self) {
+        // A sandbox path being finished gives way to the full run.
+        if self
+            .puzzle()
+            .search
+            .as_ref()
+            .is_some_and(|search| search.finishing.is_none())
+        {
+            self.status = Status::Error;
+            self.log_message = "Already solving".to_string();
+            return;
+        }
+
+        let (sender, receiver) = channel();
+        let exitus = self.puzzle().exitus.clone();
+        let reagents = self.puzzle().reagents.clone();
+        let options = self.solver_options;
+
+        thread::spawn(move || {
+            let progress_sender = sender.clone();
+            let report = solve_with_progress(&exitus, &reagents, &options, move |progress| {
+                // The app may have quit, nothing left to tell then.
+                let _ = progress_sender.send(SearchMessage::Progress(progress.clone()));
+            });
+            let _ = sender.send(SearchMessage::Done(report));
+        });
+
+        let puzzle = self.puzzle_mut();
+        puzzle.search = Some(RunningSearch {
+            receiver,
+            progress: Vec::new(),
+            options,
+            started: Instant::now(),
+            finishing: None,
+        });
+        puzzle.solver_log = vec!["Searching...".to_string()];
+        puzzle.solver_tab = 0;
+        puzzle.solver_scroll = 0;
+    }
+
+    /// Takes in the progress of the searches of every puzzle, solving in
+    /// the background or not.
+    pub fn poll_search(&mut self) {
+        self.puzzles.iter_mut().for_each(Puzzle::poll_search);
+    }
+
+    /// Opens the sandbox on the loaded puzzle, or closes it.
+    pub fn toggle_sandbox(&mut self) {
+        if self.puzzle().sandbox.is_some() {
+            self.puzzle_mut().sandbox = None;
+        } else if self.puzzle().reagents.is_empty() {
+            self.status = Status::Error;
+            self.log_message = "Load reagents before opening the sandbox".to_string();
+        } else {
+            self.puzzle_mut().sandbox = Some(Sandbox::new());
+            self.switch_active_block(ActiveBlock::ReagentOutput);
+        }
+    }
+
+    /// Applies the selected reagent in the sandbox.
+    pub fn sandbox_apply(&mut self) {
+        let puzzle = self.puzzle_mut();
+        if let Some(sandbox) = &mut puzzle.sandbox {
+            if let Some(reagent) = puzzle.reagents.get(puzzle.selected_reagent) {
+                sandbox.apply(reagent);
+            }
+        }
+    }
+
+    pub fn select_previous_reagent(&mut self) {
+        self.select_reagent(self.puzzle().selected_reagent.saturating_sub(1));
+    }
+
+    pub fn select_next_reagent(&mut self) {
+        self.select_reagent(self.puzzle().selected_reagent.saturating_add(1));
+    }
+
+    /// Selects a reagent and scrolls the reagent pane to keep it in view.
+    pub fn select_reagent(&mut self, index: usize) {
+        let height = self.areas.reagents.height as usize;
+        let puzzle = self.puzzle_mut();
+        puzzle.selected_reagent = index.min(puzzle.reagents.len().saturating_sub(1));
+
+        // Every reagent takes two lines, the exitus comes first.
+        let line = (puzzle.selected_reagent + 1) * 2;
+        if line < puzzle.reagent_scroll {
+            puzzle.reagent_scroll = line;
+        } else if height > 0 && line >= puzzle.reagent_scroll + height {
+            puzzle.reagent_scroll = line + 1 - height;
+        }
+    }
+
+    /// Index of the reagent drawn on screen row `row`, if any.
+    pub fn reagent_at_row(&self, row: u16) -> Option<usize> {
+        let puzzle = self.puzzle();
+        let line = (row.checked_sub(self.areas.reagents.y)? as usize) + puzzle.reagent_scroll;
+        match line % 2 {
+            0 => (line / 2)
+                .checked_sub(1)
+                .filter(|&i| i < puzzle.reagents.len()),
+            _ => None,
+        }
+    }
+
+    pub fn scroll_reagents_up(&mut self, lines: usize) {
+        let puzzle = self.puzzle_mut();
+        puzzle.reagent_scroll = puzzle.reagent_scroll.saturating_sub(lines);
+    }
+
+    /// Scrolls down; the renderer clamps the offset to the content height.
+    pub fn scroll_reagents_down(&mut self, lines: usize) {
+        let puzzle = self.puzzle_mut();
+        puzzle.reagent_scroll = puzzle.reagent_scroll.saturating_add(lines);
+    }
+
+    pub fn selected_setting(&self) -> Setting {
+        Setting::ALL[self.selected_setting]
+    }
+
+    pub fn select_setting(&mut self, index: usize) {
+        self.selected_setting = index.min(Setting::ALL.len() - 1);
+    }
+
+    /// Steps the selected setting up or down, never below one; the strategy
+    /// and tree recording just toggle.
+    pub fn adjust_setting(&mut self, increase: bool) {
+        let step = |value: usize, by: usize| match increase {
+            true => value.saturating_add(by),
+            false => value.saturating_sub(by).max(1),
+        };
+
+        let setting = self.selected_setting();
+        let options = &mut self.solver_options;
+        match setting {
+            Setting::Strategy => {
+                options.strategy = match options.strategy {
+                    Strategy::Priority => Strategy::Beam,
+                    Strategy::Beam => Strategy::Priority,
                 }
             }
+            Setting::MaxDepth => options.max_depth = step(options.max_depth, 1),
+            Setting::MaxIterations => options.max_iterations = step(options.max_iterations, 250),
+            Setting::BeamWidth => options.beam_width = step(options.beam_width, 5),
+            Setting::RecordTree => options.record_tree = !options.record_tree,
         }
     }
+
+    /// Sets a solver option from text, as typed in the command palette.
+    pub fn set_setting(&mut self, setting: Setting, value: &str) -> Result<(), String> {
+        setting.set(&mut self.solver_options, value)
+    }
+
+    /// Asks the solver to finish the path picked in the sandbox, on a
+    /// background thread like [`App::find_solutions`].
+    pub fn sandbox_finish(&mut self) {
+        let puzzle = &mut self.puzzles[self.active_puzzle];
+        let Some(sandbox) = &mut puzzle.sandbox else {
+            return;
+        };
+        if puzzle
+            .search
+            .as_ref()
+            .is_some_and(|search| search.finishing.is_none())
+        {
+            self.status = Status::Error;
+            self.log_message = "Already solving".to_string();
+            return;
+        }
+
+        let (sender, receiver) = channel();
+        let exitus = puzzle.exitus.clone();
+        let reagents = puzzle.reagents.clone();
+        let prefix = sandbox.current().reagent_path;
+        let options = self.solver_options;
+        sandbox.finished = None;
+
+        let thread_prefix = prefix.clone();
+        thread::spawn(move || {
+            let path = finish_path(&exitus, &reagents, &thread_prefix, &options);
+            // The app may have quit, nothing left to tell then.
+            let _ = sender.send(SearchMessage::Finished(path));
+        });
+
+        puzzle.search = Some(RunningSearch {
+            receiver,
+            progress: Vec::new(),
+            options,
+            started: Instant::now(),
+            finishing: Some(prefix),
+        });
+    }
+
+    /// Titles of the solver log tabs: the overview, then one per solution.
+    pub fn solver_tab_titles(&self) -> Vec<String> {
+        let mut titles = vec!["Overview".to_string()];
+
+        if let Some(report) = &self.puzzle().solve_report {
+            titles.extend(report.solutions().map(|result| {
+                let steps = result.path.as_ref().map_or(0, |p| p.len());
+                format!("{} ({})", result.start.name, steps)
+            }));
+        }
+
+        titles
+    }
+
+    /// Lines of the selected solver log tab.
+    pub fn solver_tab_lines(&self) -> Vec<String> {
+        let puzzle = self.puzzle();
+        let solution = puzzle
+            .solve_report
+            .as_ref()
+            .zip(puzzle.solver_tab.checked_sub(1))
+            .and_then(|(report, i)| report.solutions().nth(i).map(|r| (report, r)));
+
+        match solution {
+            Some((report, result)) => report.trace_lines(result),
+            None => puzzle
+                .solver_log
+                .join("\n")
+                .lines()
+                .map(|l| l.to_string())
+                .collect(),
+        }
+    }
+
+    pub fn select_solver_tab(&mut self, tab: usize) {
+        if tab < self.solver_tab_titles().len() {
+            let puzzle = self.puzzle_mut();
+            puzzle.solver_tab = tab;
+            puzzle.solver_scroll = 0;
+        }
+    }
+
+    /// Solver log tab whose title is drawn at column `column`.
+    pub fn solver_tab_at_column(&self, column: u16) -> Option<usize> {
+        tab_at_column(&self.solver_tab_titles(), self.areas.solver_tabs.x, column)
+    }
+
+    /// Solution tab for the overview line drawn on screen row `row`, if the
+    /// line belongs to a found path.
+    pub fn solution_tab_at_row(&self, row: u16) -> Option<usize> {
+        let puzzle = self.puzzle();
+        let report = puzzle.solve_report.as_ref()?;
+        // A running search shows its progress instead of the last overview.
+        if puzzle
+            .search
+            .as_ref()
+            .is_some_and(|s| s.finishing.is_none())
+        {
+            return None;
+        }
+        let line = (row.checked_sub(self.areas.solver_content.y)? as usize) + puzzle.solver_scroll;
+
+        let result = report.result_at_log_line(line)?;
+        report.results[result].path.as_ref()?;
+        let earlier = report.results[..result].iter().filter(|r| r.path.is_some());
+        Some(earlier.count() + 1)
+    }
+
+    pub fn next_solver_tab(&mut self) {
+        let tabs = self.solver_tab_titles().len();
+        let puzzle = self.puzzle_mut();
+        puzzle.solver_tab = (puzzle.solver_tab + 1) % tabs;
+        puzzle.solver_scroll = 0;
+    }
+
+    pub fn previous_solver_tab(&mut self) {
+        let tabs = self.solver_tab_titles().len();
+        let puzzle = self.puzzle_mut();
+        puzzle.solver_tab = (puzzle.solver_tab + tabs - 1) % tabs;
+        puzzle.solver_scroll = 0;
+    }
+
+    pub fn scroll_solver_log_up(&mut self, lines: usize) {
+        let puzzle = self.puzzle_mut();
+        puzzle.solver_scroll = puzzle.solver_scroll.saturating_sub(lines);
+    }
+
+    /// Scrolls down; the renderer clamps the offset to the content height.
+    pub fn scroll_solver_log_down(&mut self, lines: usize) {
+        let puzzle = self.puzzle_mut();
+        puzzle.solver_scroll = puzzle.solver_scroll.saturating_add(lines);
+    }
+
+    /// Is the end of the selected solver log tab in view?
+    pub fn solver_log_at_end(&self) -> bool {
+        let height = self.areas.solver_content.height as usize;
+        self.puzzle().solver_scroll + height >= self.solver_tab_lines().len()
+    }
+}
+
+/// Tab whose title is drawn at column `column` of a tab bar starting at `x`.
+fn tab_at_column(titles: &[String], mut x: u16, column: u16) -> Option<usize> {
+    for (i, title) in titles.iter().enumerate() {
+        // Each title is padded by a space on both sides and followed by a divider.
+        let width = title.chars().count() as u16 + 2;
+        if (x..x + width).contains(&column) {
+            return Some(i);
+        }
+        x += width + 1;
+    }
+
+    None
+}
+
+/// Absolute path of a file, as written to the settings file.
+fn absolute_path(file_name: &str) -> PathBuf {
+    Path::new(file_name)
+        .canonicalize()
+        .unwrap_or_else(|_| PathBuf::from(file_name))
+}
+
+/// Last modification time of a file, `None` if it cannot be read.
+fn file_mtime(file_name: &str) -> Option<SystemTime> {
+    fs::metadata(file_name).and_then(|m| m.modified()).ok()
 }
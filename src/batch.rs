@@ -0,0 +1,188 @@
+use crate::reagent::{load_reagents, read_puzzle};
+use crate::solver::{is_valid_path, solve, SolverOptions};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Suffix of the files holding reference solutions, e.g. `reagents3_solution.txt`.
+pub const SOLUTION_SUFFIX: &str = "_solution.txt";
+
+/// How a puzzle fared compared to its reference solution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The path found is one of the reference solutions.
+    Matched,
+    /// A valid path was found that is no longer than the reference.
+    Solved,
+    /// A valid path was found, but it is longer than the reference.
+    Longer,
+    /// A reference exists, but the solver found nothing.
+    Missed,
+    /// The path found does not produce the exitus.
+    Invalid,
+    /// No reference and no path.
+    Unsolved,
+    /// The puzzle file could not be read or parsed.
+    Error,
+}
+
+impl Outcome {
+    pub fn is_solved(&self) -> bool {
+        matches!(self, Outcome::Matched | Outcome::Solved)
+    }
+
+    pub fn is_regression(&self) -> bool {
+        matches!(self, Outcome::Longer | Outcome::Missed | Outcome::Invalid)
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Outcome::Matched => "matched",
+            Outcome::Solved => "solved",
+            Outcome::Longer => "longer",
+            Outcome::Missed => "missed",
+            Outcome::Invalid => "invalid",
+            Outcome::Unsolved => "unsolved",
+            Outcome::Error => "error",
+        };
+        f.pad(label)
+    }
+}
+
+/// Result of solving one puzzle file of a batch.
+#[derive(Clone, Debug)]
+pub struct BatchEntry {
+    pub puzzle: PathBuf,
+    pub outcome: Outcome,
+    pub path: Option<Vec<String>>,
+    pub expected: Vec<Vec<String>>,
+    /// Reference solution file the path was compared with.
+    pub reference: Option<PathBuf>,
+    pub elapsed: Duration,
+    pub message: Option<String>,
+}
+
+impl BatchEntry {
+    /// Length of the shortest reference solution.
+    pub fn expected_len(&self) -> Option<usize> {
+        self.expected.iter().map(|p| p.len()).min()
+    }
+}
+
+/// Lists the puzzle files of `dir`, skipping reference solution files.
+pub fn find_puzzles(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut puzzles: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            name.ends_with(".txt") && !name.ends_with(SOLUTION_SUFFIX)
+        })
+        .collect();
+
+    puzzles.sort();
+    Ok(puzzles)
+}
+
+/// Path of the reference solution belonging to `puzzle`.
+pub fn solution_path(puzzle: &Path) -> PathBuf {
+    let stem = puzzle.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    puzzle.with_file_name(format!("{}{}", stem, SOLUTION_SUFFIX))
+}
+
+/// Finds the reference solution of `puzzle`: `<stem>_solution.txt`, or
+/// failing that the stem without its last `_` part, so `wiki_eg.txt` is
+/// checked against `wiki_solution.txt`.
+pub fn find_solution(puzzle: &Path) -> Option<PathBuf> {
+    let stem = puzzle.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let shortened = stem
+        .rsplit_once('_')
+        .map(|(base, _)| puzzle.with_file_name(format!("{}{}", base, SOLUTION_SUFFIX)));
+    std::iter::once(solution_path(puzzle))
+        .chain(shortened)
+        .find(|path| path.is_file())
+}
+
+/// Parses a reference solution file.
+///
+/// Every non-empty line is one solution, written either as `A, B, C` or
+/// as a bracketed list like `["A", "B", "C"]`.
+pub fn parse_solutions(contents: &str) -> Vec<Vec<String>> {
+    contents
+        .lines()
+        .map(|line| line.trim().trim_start_matches('[').trim_end_matches(']'))
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            line.split(',')
+                .map(|name| name.trim().trim_matches('"').to_string())
+                .filter(|name| !name.is_empty())
+                .collect::<Vec<String>>()
+        })
+        .filter(|path| !path.is_empty())
+        .collect()
+}
+
+/// Solves `puzzle` and compares the result with its reference solution, if any.
+pub fn run_puzzle(puzzle: &Path, options: &SolverOptions) -> BatchEntry {
+    let reference = find_solution(puzzle);
+    let mut expected = match reference
+        .as_ref()
+        .map(|r| load_reagents(&r.to_string_lossy()))
+    {
+        Some(Ok(contents)) => parse_solutions(&contents),
+        _ => Vec::new(),
+    };
+    expected.dedup();
+
+    let mut entry = BatchEntry {
+        puzzle: puzzle.to_path_buf(),
+        outcome: Outcome::Error,
+        path: None,
+        expected,
+        reference,
+        elapsed: Duration::ZERO,
+        message: None,
+    };
+
+    if entry.reference.is_none() {
+        let name = solution_path(puzzle);
+        let name = name.file_name().unwrap_or_default().to_string_lossy();
+        entry.message = Some(format!("no reference solution, {} not found", name));
+    }
+
+    let (exitus, reagents) = match read_puzzle(&puzzle.to_string_lossy()) {
+        Ok(puzzle) => puzzle,
+        Err(e) => {
+            entry.message = Some(e);
+            return entry;
+        }
+    };
+
+    let start_time = Instant::now();
+    let report = solve(&exitus, &reagents, options);
+    entry.elapsed = start_time.elapsed();
+    entry.path = report.best_path().cloned();
+
+    entry.outcome = match (&entry.path, entry.expected_len()) {
+        (Some(path), _) if !is_valid_path(&exitus, &reagents, path) => Outcome::Invalid,
+        (Some(path), _) if entry.expected.contains(path) => Outcome::Matched,
+        (Some(path), Some(expected_len)) if path.len() > expected_len => Outcome::Longer,
+        (Some(_), _) => Outcome::Solved,
+        (None, Some(_)) => Outcome::Missed,
+        (None, None) => Outcome::Unsolved,
+    };
+
+    entry
+}
+
+/// Solves every puzzle in `dir`.
+pub fn run_batch(dir: &Path, options: &SolverOptions) -> io::Result<Vec<BatchEntry>> {
+    Ok(find_puzzles(dir)?
+        .iter()
+        .map(|puzzle| run_puzzle(puzzle, options))
+        .collect())
+}
@@ -0,0 +1,342 @@
+use crate::batch::{run_batch, solution_path, BatchEntry, Outcome};
+use crate::export::{render_report, ExportFormat};
+use crate::generator::{generate, GeneratorOptions, FAMILIES};
+use crate::reagent::{read_puzzle, Reagent, STDIN_FILE_NAME};
+use crate::solver::{
+    solve, SolveReport, SolverOptions, Strategy, BEAM_WIDTH, MAX_DEPTH, MAX_ITERATIONS,
+};
+use clap::{Args, Parser, Subcommand};
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Exit code when at least one solution was found.
+pub const EXIT_SOLVED: u8 = 0;
+
+/// Exit code when the puzzle was read but no solution was found, or when a
+/// batch run regressed against the reference solutions.
+pub const EXIT_UNSOLVED: u8 = 1;
+
+/// Exit code when the puzzle could not be read or parsed.
+pub const EXIT_ERROR: u8 = 2;
+
+/// Command line interface.
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "Mutagen puzzle solver with a terminal user interface"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Settings file to use instead of the one in the config directory
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Start the terminal user interface (default)
+    Tui {
+        /// Puzzle file to load on startup, `-` for stdin
+        file: Option<String>,
+    },
+    /// Solve a puzzle file and print the result without starting the interface
+    Solve(SolveArgs),
+    /// Solve every puzzle in a directory and compare against the reference solutions
+    Batch(BatchArgs),
+    /// Generate a random puzzle with a planted solution
+    Generate(GenerateArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct SolveArgs {
+    /// Puzzle file to solve, `-` or omitted for stdin
+    #[arg(default_value = STDIN_FILE_NAME)]
+    pub file: String,
+    /// Print the result as JSON
+    #[arg(long)]
+    pub json: bool,
+    /// Write the nodes the priority search expanded as a Graphviz DOT graph
+    #[arg(long, value_name = "FILE")]
+    pub dot: Option<PathBuf>,
+    #[command(flatten)]
+    pub solver: SolverArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct BatchArgs {
+    /// Directory holding the puzzle files and their `*_solution.txt` files
+    #[arg(default_value = "examples")]
+    pub dir: PathBuf,
+    #[command(flatten)]
+    pub solver: SolverArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct GenerateArgs {
+    /// Write the puzzle here, plus its `*_solution.txt` next to it, instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    /// Seed for reproducible puzzles, random when omitted
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Comma separated atoms to build reagents from
+    #[arg(long, value_delimiter = ',')]
+    pub alphabet: Vec<String>,
+    /// Number of made-up atoms when no alphabet is given, at most 936
+    #[arg(long, default_value_t = 24)]
+    pub alphabet_size: usize,
+    /// Number of reagents
+    #[arg(long, default_value_t = 15)]
+    pub reagents: usize,
+    /// Minimum atoms per reagent
+    #[arg(long, default_value_t = 3)]
+    pub min_atoms: usize,
+    /// Maximum atoms per reagent
+    #[arg(long, default_value_t = 7)]
+    pub max_atoms: usize,
+    /// Chance of an atom being a negative
+    #[arg(long, default_value_t = 0.3)]
+    pub negative_ratio: f64,
+    /// Comma separated reagent families
+    #[arg(long, value_delimiter = ',', default_values_t = FAMILIES.map(String::from))]
+    pub families: Vec<String>,
+    /// Number of reagents in the planted solution
+    #[arg(long, default_value_t = 5)]
+    pub path_length: usize,
+}
+
+impl GenerateArgs {
+    pub fn options(&self) -> GeneratorOptions {
+        GeneratorOptions {
+            alphabet: self.alphabet.clone(),
+            alphabet_size: self.alphabet_size,
+            reagent_count: self.reagents,
+            min_atoms: self.min_atoms,
+            max_atoms: self.max_atoms,
+            negative_ratio: self.negative_ratio,
+            families: self.families.clone(),
+            path_length: self.path_length,
+            seed: self.seed.unwrap_or_else(rand::random),
+        }
+    }
+}
+
+/// Solver settings shared by the headless commands.
+#[derive(Clone, Debug, Args)]
+pub struct SolverArgs {
+    /// Search strategy (priority or beam)
+    #[arg(long, default_value_t = Strategy::Priority)]
+    pub strategy: Strategy,
+    /// Maximum number of reagents in a path
+    #[arg(long, default_value_t = MAX_DEPTH)]
+    pub max_depth: usize,
+    /// Maximum number of nodes expanded per start reagent
+    #[arg(long, default_value_t = MAX_ITERATIONS)]
+    pub max_iterations: usize,
+    /// Candidates kept per depth by the beam strategy
+    #[arg(long, default_value_t = BEAM_WIDTH)]
+    pub beam_width: usize,
+}
+
+impl SolverArgs {
+    pub fn options(&self) -> SolverOptions {
+        SolverOptions {
+            strategy: self.strategy,
+            max_depth: self.max_depth,
+            max_iterations: self.max_iterations,
+            beam_width: self.beam_width,
+            record_tree: false,
+        }
+    }
+}
+
+/// Runs the `solve` subcommand.
+pub fn run_solve(args: &SolveArgs) -> ExitCode {
+    if args.dot.is_some() && args.solver.strategy != Strategy::Priority {
+        eprintln!("error: --dot needs the priority strategy, only it records the search tree");
+        return ExitCode::from(EXIT_ERROR);
+    }
+
+    let (exitus, reagents) = match read_puzzle(&args.file) {
+        Ok(puzzle) => puzzle,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+
+    let options = SolverOptions {
+        record_tree: args.dot.is_some(),
+        ..args.solver.options()
+    };
+    let report = solve(&exitus, &reagents, &options);
+
+    if let Some(dot) = &args.dot {
+        let contents = render_report(ExportFormat::Dot, &args.file, &report);
+        if let Err(e) = fs::write(dot, contents) {
+            eprintln!("error: cannot write {}: {}", dot.display(), e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    }
+
+    if args.json {
+        println!("{}", report_json(&args.file, &exitus, &options, &report));
+    } else {
+        for line in report.log_lines() {
+            println!("{}", line);
+        }
+    }
+
+    match report.is_solved() {
+        true => ExitCode::from(EXIT_SOLVED),
+        false => ExitCode::from(EXIT_UNSOLVED),
+    }
+}
+
+/// Serialises a solver run into the JSON document printed by `solve --json`.
+pub fn report_json(
+    file_name: &str,
+    exitus: &Reagent,
+    options: &SolverOptions,
+    report: &SolveReport,
+) -> serde_json::Value {
+    json!({
+        "file": file_name,
+        "exitus": exitus.atoms,
+        "strategy": options.strategy.to_string(),
+        "max_depth": options.max_depth,
+        "solved": report.is_solved(),
+        "useless_reagents": report
+            .useless_reagents
+            .iter()
+            .map(|r| r.name.clone())
+            .collect::<Vec<String>>(),
+        "viable_starts": report
+            .viable_starts
+            .iter()
+            .map(|r| json!({ "name": r.name, "score": r.score }))
+            .collect::<Vec<serde_json::Value>>(),
+        "results": report
+            .results
+            .iter()
+            .map(|r| json!({
+                "start": r.start.name,
+                "path": r.path,
+                "micros": r.elapsed.as_micros() as u64,
+                "nodes_expanded": r.stats.nodes_expanded,
+                "best_match": r.stats.best_match,
+            }))
+            .collect::<Vec<serde_json::Value>>(),
+    })
+}
+
+/// Runs the `generate` subcommand.
+pub fn run_generate(args: &GenerateArgs) -> ExitCode {
+    let options = args.options();
+    let puzzle = match generate(&options) {
+        Ok(puzzle) => puzzle,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+
+    match &args.output {
+        Some(output) => {
+            let written = fs::write(output, puzzle.to_file_string())
+                .and_then(|_| fs::write(solution_path(output), puzzle.solution_file_string()));
+            if let Err(e) = written {
+                eprintln!("error: cannot write {}: {}", output.display(), e);
+                return ExitCode::from(EXIT_ERROR);
+            }
+        }
+        None => print!("{}", puzzle.to_file_string()),
+    }
+
+    eprintln!("seed {}: {}", options.seed, puzzle.solution.join(", "));
+    ExitCode::from(EXIT_SOLVED)
+}
+
+/// Runs the `batch` subcommand.
+pub fn run_batch_command(args: &BatchArgs) -> ExitCode {
+    let entries = match run_batch(&args.dir, &args.solver.options()) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("error: cannot read {}: {}", args.dir.display(), e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+
+    print_batch_table(&entries);
+
+    match entries.iter().any(|e| e.outcome.is_regression()) {
+        true => ExitCode::from(EXIT_UNSOLVED),
+        false => ExitCode::from(EXIT_SOLVED),
+    }
+}
+
+fn print_batch_table(entries: &[BatchEntry]) {
+    let name_width = entries
+        .iter()
+        .map(|e| e.puzzle.file_name().map_or(0, |n| n.len()))
+        .max()
+        .unwrap_or(0)
+        .max("puzzle".len());
+
+    println!(
+        "{:<name_width$}  {:<8}  {:>5}  {:>8}  {:>10}",
+        "puzzle", "status", "found", "expected", "time (ms)"
+    );
+
+    for entry in entries {
+        let name = entry
+            .puzzle
+            .file_name()
+            .map_or(String::new(), |n| n.to_string_lossy().to_string());
+        let found = entry
+            .path
+            .as_ref()
+            .map_or("-".to_string(), |p| p.len().to_string());
+        let expected = entry
+            .expected_len()
+            .map_or("-".to_string(), |l| l.to_string());
+
+        println!(
+            "{:<name_width$}  {:<8}  {:>5}  {:>8}  {:>10.1}",
+            name,
+            entry.outcome,
+            found,
+            expected,
+            entry.elapsed.as_secs_f64() * 1000.0,
+        );
+
+        if let Some(message) = &entry.message {
+            println!("  \u{21B3}{}", message);
+        }
+    }
+
+    let count = |f: fn(&BatchEntry) -> bool| entries.iter().filter(|e| f(e)).count();
+    let solved = count(|e| e.outcome.is_solved());
+    let regressed = count(|e| e.outcome.is_regression());
+    let errors = count(|e| e.outcome == Outcome::Error);
+    let unsolved = entries.len() - solved - regressed - errors;
+    let unreferenced = count(|e| e.outcome != Outcome::Error && e.reference.is_none());
+
+    print!(
+        "\n{} puzzles: {} solved, {} unsolved, {} regressed",
+        entries.len(),
+        solved,
+        unsolved,
+        regressed
+    );
+    match errors {
+        0 => println!(),
+        _ => println!(", {} unreadable", errors),
+    }
+    if unreferenced > 0 {
+        println!("{} without a reference solution", unreferenced);
+    }
+}
@@ -0,0 +1,183 @@
+// Module: clipboard
+use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// How a copied reagent path is written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CopyFormat {
+    /// `A, B, C`
+    #[default]
+    Comma,
+    /// One reagent per line.
+    Lines,
+}
+
+impl CopyFormat {
+    pub const ALL: [CopyFormat; 2] = [CopyFormat::Comma, CopyFormat::Lines];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CopyFormat::Comma => "comma",
+            CopyFormat::Lines => "lines",
+        }
+    }
+
+    pub fn format(self, path: &[String]) -> String {
+        match self {
+            CopyFormat::Comma => path.join(", "),
+            CopyFormat::Lines => path.join("\n"),
+        }
+    }
+}
+
+impl fmt::Display for CopyFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for CopyFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CopyFormat::ALL
+            .into_iter()
+            .find(|format| format.name() == s.to_lowercase())
+            .ok_or_else(|| format!("unknown copy format '{}' (expected comma or lines)", s))
+    }
+}
+
+/// Where copied text goes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClipboardMethod {
+    /// The system clipboard, through the terminal.
+    #[default]
+    Osc52,
+    /// A file next to the puzzle, for terminals without OSC 52.
+    File,
+}
+
+impl ClipboardMethod {
+    pub const ALL: [ClipboardMethod; 2] = [ClipboardMethod::Osc52, ClipboardMethod::File];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ClipboardMethod::Osc52 => "osc52",
+            ClipboardMethod::File => "file",
+        }
+    }
+}
+
+impl fmt::Display for ClipboardMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ClipboardMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ClipboardMethod::ALL
+            .into_iter()
+            .find(|method| method.name() == s.to_lowercase())
+            .ok_or_else(|| format!("unknown clipboard method '{}' (expected osc52 or file)", s))
+    }
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard base64 with padding, as OSC 52 expects.
+pub fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => encoded.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Escape sequence asking the terminal to put `text` on the clipboard.
+pub fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64_encode(text.as_bytes()))
+}
+
+/// Sends `text` to the clipboard through the terminal the interface is
+/// drawn on.
+///
+/// Terminals without OSC 52 drop the sequence silently, so success only
+/// means it was written.
+pub fn copy_osc52(text: &str) -> io::Result<()> {
+    let mut stderr = io::stderr();
+    stderr.write_all(osc52_sequence(text).as_bytes())?;
+    stderr.flush()
+}
+
+/// File copied text is written to instead of the clipboard: `reagents3.txt`
+/// gives `reagents3_path.txt` next to it.
+pub fn fallback_path(puzzle_file: Option<&str>) -> String {
+    let stem = puzzle_file
+        .and_then(|file| Path::new(file).file_stem())
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "solver".to_string());
+    let file_name = format!("{}_path.txt", stem);
+
+    match puzzle_file.and_then(|file| Path::new(file).parent()) {
+        Some(dir) => dir.join(file_name).to_string_lossy().into_owned(),
+        None => file_name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_the_rfc_4648_test_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (input, expected) in vectors {
+            assert_eq!(base64_encode(input.as_bytes()), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn encodes_every_sextet() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let encoded = base64_encode(&bytes);
+
+        assert_eq!(encoded.len(), 344);
+        assert!(encoded.starts_with("AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8g"));
+        assert!(encoded.ends_with("+fr7/P3+/w=="));
+        assert_eq!(base64_encode(&[0xfb, 0xff]), "+/8=");
+        assert_eq!(base64_encode(&[0xff, 0xff, 0xff]), "////");
+    }
+
+    #[test]
+    fn wraps_the_text_in_osc_52() {
+        assert_eq!(osc52_sequence("foobar"), "\x1b]52;c;Zm9vYmFy\x07");
+    }
+}
@@ -28,6 +28,14 @@ impl std::fmt::Debug for Combinator {
 }
 
 impl Combinator {
+    /// A combinator holding only `start`, the first reagent of a path.
+    pub fn from_start(start: &Reagent) -> Self {
+        Self {
+            sequence: start.atoms.clone(),
+            reagent_path: vec![start.name.clone()],
+        }
+    }
+
     pub fn add_reagent(&mut self, reagent: &Reagent) {
         self.reagent_path.push(reagent.name.clone());
 
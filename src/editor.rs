@@ -0,0 +1,193 @@
+/// Multi-line text editor backing the reagent pane.
+///
+/// Each line holds one reagent; the cursor is kept as a line and a
+/// character index so multi-byte input never splits a char.
+#[derive(Clone, Debug)]
+pub struct ReagentEditor {
+    /// Text of every line, without line breaks.
+    pub lines: Vec<String>,
+    /// Line of the cursor.
+    pub row: usize,
+    /// Character index of the cursor within its line.
+    pub column: usize,
+    /// First line shown in the pane.
+    pub scroll: usize,
+}
+
+impl Default for ReagentEditor {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl ReagentEditor {
+    /// Constructs a new instance of [`ReagentEditor`] holding `text`.
+    pub fn new(text: &str) -> Self {
+        let mut lines: Vec<String> = text.lines().map(|l| l.to_string()).collect();
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+
+        Self {
+            lines,
+            row: 0,
+            column: 0,
+            scroll: 0,
+        }
+    }
+
+    /// The edited text, one reagent per line.
+    pub fn text(&self) -> String {
+        let mut text = self.lines.join("\n");
+        text.push('\n');
+        text
+    }
+
+    fn line_len(&self) -> usize {
+        self.lines[self.row].chars().count()
+    }
+
+    fn byte_index(&self) -> usize {
+        let line = &self.lines[self.row];
+        line.char_indices()
+            .map(|(i, _)| i)
+            .nth(self.column)
+            .unwrap_or(line.len())
+    }
+
+    pub fn move_left(&mut self) {
+        if self.column > 0 {
+            self.column -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.column = self.line_len();
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        if self.column < self.line_len() {
+            self.column += 1;
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.column = 0;
+        }
+    }
+
+    pub fn move_up(&mut self) {
+        if self.row > 0 {
+            self.row -= 1;
+            self.column = self.column.min(self.line_len());
+        }
+    }
+
+    pub fn move_down(&mut self) {
+        if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.column = self.column.min(self.line_len());
+        }
+    }
+
+    /// Moves the cursor to a line and column, clamped to the text.
+    pub fn move_to(&mut self, row: usize, column: usize) {
+        self.row = row.min(self.lines.len() - 1);
+        self.column = column.min(self.line_len());
+    }
+
+    pub fn move_home(&mut self) {
+        self.column = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.column = self.line_len();
+    }
+
+    pub fn insert_char(&mut self, new_char: char) {
+        let index = self.byte_index();
+        self.lines[self.row].insert(index, new_char);
+        self.column += 1;
+    }
+
+    /// Inserts `text` at the cursor, starting new lines at its line breaks.
+    pub fn insert_str(&mut self, text: &str) {
+        for c in text.chars() {
+            match c {
+                '\n' => self.insert_newline(),
+                c if c.is_control() => {}
+                c => self.insert_char(c),
+            }
+        }
+    }
+
+    /// Splits the line at the cursor, starting a new reagent.
+    pub fn insert_newline(&mut self) {
+        let index = self.byte_index();
+        let rest = self.lines[self.row].split_off(index);
+        self.lines.insert(self.row + 1, rest);
+        self.row += 1;
+        self.column = 0;
+    }
+
+    /// Deletes the character before the cursor, joining lines at the start of one.
+    pub fn delete_char(&mut self) {
+        if self.column > 0 {
+            self.column -= 1;
+            let index = self.byte_index();
+            self.lines[self.row].remove(index);
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.column = self.line_len();
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    /// Deletes the character under the cursor, joining lines at the end of one.
+    pub fn delete_char_forward(&mut self) {
+        if self.column < self.line_len() {
+            let index = self.byte_index();
+            self.lines[self.row].remove(index);
+        } else if self.row + 1 < self.lines.len() {
+            let line = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    /// Deletes the atom (or reagent name) before the cursor.
+    pub fn delete_atom(&mut self) {
+        let chars: Vec<char> = self.lines[self.row].chars().collect();
+        let mut start = self.column;
+
+        while start > 0 && chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        while start > 0 && !chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+
+        self.lines[self.row] = chars[..start]
+            .iter()
+            .chain(chars[self.column..].iter())
+            .collect();
+        self.column = start;
+    }
+
+    /// Deletes the reagent on the cursor line.
+    pub fn delete_line(&mut self) {
+        if self.lines.len() > 1 {
+            self.lines.remove(self.row);
+            self.row = self.row.min(self.lines.len() - 1);
+        } else {
+            self.lines[0].clear();
+        }
+        self.column = self.column.min(self.line_len());
+    }
+
+    /// Keeps the cursor line within a pane `height` lines tall.
+    pub fn scroll_to_cursor(&mut self, height: usize) {
+        if self.row < self.scroll {
+            self.scroll = self.row;
+        } else if height > 0 && self.row >= self.scroll + height {
+            self.scroll = self.row + 1 - height;
+        }
+    }
+}
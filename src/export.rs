@@ -0,0 +1,308 @@
+use crate::app::Setting;
+use crate::cli::report_json;
+use crate::reagent::Reagent;
+use crate::solver::{unremovable_atoms, SolveReport, SolverOptions};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// File format of an exported solver report.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Markdown,
+    /// A single page with its styles inline.
+    Html,
+    /// Graphviz graph of the nodes the priority search expanded.
+    Dot,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 4] = [
+        ExportFormat::Json,
+        ExportFormat::Markdown,
+        ExportFormat::Html,
+        ExportFormat::Dot,
+    ];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+            ExportFormat::Dot => "dot",
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ExportFormat::ALL
+            .into_iter()
+            .find(|format| format.extension() == s.to_lowercase())
+            .ok_or_else(|| {
+                format!(
+                    "unknown export format '{}' (expected json, md, html or dot)",
+                    s
+                )
+            })
+    }
+}
+
+/// Default export file next to the puzzle: `reagents3.txt` becomes
+/// `reagents3_report.json`.
+pub fn report_path(puzzle_file: Option<&str>, format: ExportFormat) -> String {
+    let stem = puzzle_file
+        .and_then(|file| Path::new(file).file_stem())
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "solver".to_string());
+    let file_name = format!("{}_report.{}", stem, format.extension());
+
+    match puzzle_file.and_then(|file| Path::new(file).parent()) {
+        Some(dir) => dir.join(file_name).to_string_lossy().into_owned(),
+        None => file_name,
+    }
+}
+
+/// Renders a solver run in the chosen format, with the puzzle and options
+/// the run was started with.
+pub fn render_report(format: ExportFormat, file_name: &str, report: &SolveReport) -> String {
+    let exitus = &report.exitus;
+    let options = &report.options;
+    match format {
+        ExportFormat::Json => {
+            let json = report_json(file_name, exitus, options, report);
+            serde_json::to_string_pretty(&json).unwrap_or_else(|_| json.to_string()) + "\n"
+        }
+        ExportFormat::Markdown => render_markdown(file_name, exitus, options, report),
+        ExportFormat::Html => render_html(file_name, exitus, options, report),
+        ExportFormat::Dot => render_dot(file_name, report),
+    }
+}
+
+/// Sentence on the atoms that are not in the exitus and that no reagent can
+/// remove, with `code` marking up the atom list for the output format.
+/// Atoms are sorted so reports come out the same every run.
+fn unremovable_note(report: &SolveReport, code: impl Fn(&str) -> String) -> String {
+    let mut atoms: Vec<String> = unremovable_atoms(&report.exitus, &report.reagents)
+        .into_iter()
+        .collect();
+    atoms.sort();
+
+    match atoms.as_slice() {
+        [] => "No reagent adds an atom that nothing can remove.".to_string(),
+        atoms => format!("Unremovable atoms: {}", code(&atoms.join(" "))),
+    }
+}
+
+fn render_markdown(
+    file_name: &str,
+    exitus: &Reagent,
+    options: &SolverOptions,
+    report: &SolveReport,
+) -> String {
+    let mut md = format!("# Solver report for {}\n\n", file_name);
+
+    md.push_str("## Puzzle\n\n");
+    md.push_str(&format!("Exitus: `{}`\n\n", exitus.atoms.join(" ")));
+    md.push_str("| Reagent | Atoms |\n|---|---|\n");
+    for reagent in &report.reagents {
+        md.push_str(&format!(
+            "| {} | `{}` |\n",
+            reagent.name,
+            reagent.atoms.join(" ")
+        ));
+    }
+
+    md.push_str("\n## Solver options\n\n");
+    for setting in Setting::ALL {
+        md.push_str(&format!(
+            "- {}: {}\n",
+            setting.label(),
+            setting.value(options)
+        ));
+    }
+
+    md.push_str("\n## Diagnostics\n\n```text\n");
+    for line in report.log_lines() {
+        md.push_str(line.trim_end());
+        md.push('\n');
+    }
+    md.push_str("```\n\n");
+    md.push_str(&unremovable_note(report, |atoms| format!("`{}`", atoms)));
+    md.push('\n');
+
+    md.push_str("\n## Solutions\n");
+    if !report.is_solved() {
+        md.push_str("\nNo solution found.\n");
+    }
+    for result in report.solutions() {
+        md.push_str(&format!("\n### Start {}\n\n```text\n", result.start.name));
+        for line in report.trace_lines(result) {
+            md.push_str(&line);
+            md.push('\n');
+        }
+        md.push_str("```\n");
+    }
+
+    md
+}
+
+const HTML_STYLE: &str =
+    "body { font-family: sans-serif; max-width: 60em; margin: 2em auto; padding: 0 1em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #999; padding: 0.2em 0.6em; text-align: left; }
+pre { background: #f4f4f4; padding: 0.8em; overflow-x: auto; }
+code, pre { font-family: monospace; }";
+
+/// Escapes text for use in HTML element content.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn html_pre(lines: &[String]) -> String {
+    let text: Vec<String> = lines
+        .iter()
+        .map(|line| escape_html(line.trim_end()))
+        .collect();
+    format!("<pre>{}</pre>\n", text.join("\n"))
+}
+
+fn render_html(
+    file_name: &str,
+    exitus: &Reagent,
+    options: &SolverOptions,
+    report: &SolveReport,
+) -> String {
+    let title = format!("Solver report for {}", escape_html(file_name));
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        title, HTML_STYLE, title
+    );
+
+    html.push_str("<h2>Puzzle</h2>\n");
+    html.push_str(&format!(
+        "<p>Exitus: <code>{}</code></p>\n",
+        escape_html(&exitus.atoms.join(" "))
+    ));
+    html.push_str("<table>\n<tr><th>Reagent</th><th>Atoms</th></tr>\n");
+    for reagent in &report.reagents {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td><code>{}</code></td></tr>\n",
+            escape_html(&reagent.name),
+            escape_html(&reagent.atoms.join(" "))
+        ));
+    }
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>Solver options</h2>\n<ul>\n");
+    for setting in Setting::ALL {
+        html.push_str(&format!(
+            "<li>{}: {}</li>\n",
+            setting.label(),
+            escape_html(&setting.value(options))
+        ));
+    }
+    html.push_str("</ul>\n");
+
+    html.push_str("<h2>Diagnostics</h2>\n");
+    html.push_str(&html_pre(&report.log_lines()));
+    let note = unremovable_note(report, |atoms| {
+        format!("<code>{}</code>", escape_html(atoms))
+    });
+    html.push_str(&format!("<p>{}</p>\n", note));
+
+    html.push_str("<h2>Solutions</h2>\n");
+    if !report.is_solved() {
+        html.push_str("<p>No solution found.</p>\n");
+    }
+    for result in report.solutions() {
+        html.push_str(&format!(
+            "<h3>Start {}</h3>\n",
+            escape_html(&result.start.name)
+        ));
+        html.push_str(&html_pre(&report.trace_lines(result)));
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Escapes text for a quoted DOT string.
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// One cluster per start holding the nodes its search expanded, with the
+/// path to the solution drawn in bold.
+fn render_dot(file_name: &str, report: &SolveReport) -> String {
+    let mut dot = format!(
+        "digraph search {{\n    label=\"{}\";\n    node [shape=box, fontname=\"monospace\"];\n",
+        escape_dot(file_name)
+    );
+
+    for (i, result) in report.results.iter().enumerate() {
+        let Some(tree) = &result.tree else {
+            continue;
+        };
+        let on_path = tree.solution_nodes();
+
+        dot.push_str(&format!(
+            "\n    subgraph cluster_{} {{\n        label=\"Start {}\";\n",
+            i,
+            escape_dot(&result.start.name)
+        ));
+
+        for (j, node) in tree.nodes.iter().enumerate() {
+            let style = match (tree.solution == Some(j), on_path.contains(&j)) {
+                (true, _) => ", style=\"bold,filled\", color=green, fillcolor=palegreen",
+                (false, true) => ", style=bold, color=green",
+                (false, false) => "",
+            };
+            dot.push_str(&format!(
+                "        s{}n{} [label=\"{}\\n{}\\nscore {:.2}, depth {}\"{}];\n",
+                i,
+                j,
+                escape_dot(&node.reagent),
+                escape_dot(&node.sequence.join(" ")),
+                node.score,
+                node.depth,
+                style
+            ));
+
+            if let Some(parent) = node.parent {
+                let style = match on_path.contains(&j) {
+                    true => " [style=bold, color=green]",
+                    false => "",
+                };
+                dot.push_str(&format!(
+                    "        s{}n{} -> s{}n{}{};\n",
+                    i, parent, i, j, style
+                ));
+            }
+        }
+
+        dot.push_str("    }\n");
+    }
+
+    dot.push_str("}\n");
+    dot
+}
@@ -0,0 +1,190 @@
+// Module: files
+use crate::batch::SOLUTION_SUFFIX;
+use crate::reagent::read_puzzle;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+
+/// Extension of puzzle files.
+pub const PUZZLE_EXTENSION: &str = "txt";
+
+/// Whether `path` looks like a puzzle rather than a reference solution.
+pub fn is_puzzle_file(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+    path.extension().is_some_and(|e| e == PUZZLE_EXTENSION) && !name.ends_with(SOLUTION_SUFFIX)
+}
+
+/// Directory to list and the start of the file name for a typed path:
+/// `examples/rea` is `examples/` and `rea`.
+fn split_typed_path(input: &str) -> (&str, &str) {
+    match input.rfind(['/', MAIN_SEPARATOR]) {
+        Some(i) => input.split_at(i + 1),
+        None => ("", input),
+    }
+}
+
+/// Completes a typed path as far as it is unambiguous.
+///
+/// Returns the completed path and, when several entries still match, their
+/// names. A unique directory gets a trailing separator so the next
+/// completion lists its contents.
+pub fn complete_path(input: &str) -> (String, Vec<String>) {
+    let (dir, prefix) = split_typed_path(input);
+    let list_dir = match dir {
+        "" => Path::new("."),
+        dir => Path::new(dir),
+    };
+
+    let mut matches: Vec<String> = match fs::read_dir(list_dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let is_dir = entry.path().is_dir();
+                let hidden = name.starts_with('.') && !prefix.starts_with('.');
+                (name.starts_with(prefix) && !hidden).then(|| match is_dir {
+                    true => format!("{}/", name),
+                    false => name,
+                })
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    matches.sort();
+
+    let completion = match matches.as_slice() {
+        [] => return (input.to_string(), Vec::new()),
+        [only] => only.clone(),
+        [first, rest @ ..] => rest.iter().fold(first.clone(), |prefix, name| {
+            prefix
+                .chars()
+                .zip(name.chars())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect()
+        }),
+    };
+
+    let candidates = match matches.len() {
+        1 => Vec::new(),
+        _ => matches,
+    };
+    (format!("{}{}", dir, completion), candidates)
+}
+
+/// Entry listed by the file browser.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BrowserEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// Popup listing the directories and puzzle files of a directory.
+#[derive(Clone, Debug)]
+pub struct FileBrowser {
+    pub dir: PathBuf,
+    pub entries: Vec<BrowserEntry>,
+    pub selected: usize,
+    /// Exitus line of the selected puzzle, or why it could not be read.
+    pub preview: Option<Result<String, String>>,
+}
+
+impl FileBrowser {
+    /// Opens the browser in `dir`.
+    pub fn new(dir: &Path) -> io::Result<Self> {
+        let mut browser = Self {
+            dir: PathBuf::new(),
+            entries: Vec::new(),
+            selected: 0,
+            preview: None,
+        };
+        browser.change_dir(dir)?;
+        Ok(browser)
+    }
+
+    /// Lists `dir`: `..` first, then directories, then puzzle files, each
+    /// sorted by name. Hidden entries are left out.
+    pub fn change_dir(&mut self, dir: &Path) -> io::Result<()> {
+        let dir = dir.canonicalize()?;
+        let mut entries: Vec<BrowserEntry> = fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let is_dir = path.is_dir();
+                (!name.starts_with('.') && (is_dir || is_puzzle_file(&path)))
+                    .then_some(BrowserEntry { name, is_dir })
+            })
+            .collect();
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then(a.name.cmp(&b.name)));
+
+        if dir.parent().is_some() {
+            entries.insert(
+                0,
+                BrowserEntry {
+                    name: "..".to_string(),
+                    is_dir: true,
+                },
+            );
+        }
+
+        self.dir = dir;
+        self.entries = entries;
+        self.select(0);
+        Ok(())
+    }
+
+    pub fn selected_entry(&self) -> Option<&BrowserEntry> {
+        self.entries.get(self.selected)
+    }
+
+    pub fn selected_path(&self) -> Option<PathBuf> {
+        self.selected_entry()
+            .map(|entry| self.dir.join(&entry.name))
+    }
+
+    /// Selects an entry and previews the exitus of the puzzle it names.
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.entries.len().saturating_sub(1));
+        self.preview = match (self.selected_entry(), self.selected_path()) {
+            (Some(entry), Some(path)) if !entry.is_dir => Some(
+                read_puzzle(&path.to_string_lossy()).map(|(exitus, reagents)| {
+                    format!("{} ({} reagents)", exitus.to_line(), reagents.len())
+                }),
+            ),
+            _ => None,
+        };
+    }
+
+    pub fn select_previous(&mut self) {
+        self.select(self.selected.saturating_sub(1));
+    }
+
+    pub fn select_next(&mut self) {
+        self.select(self.selected + 1);
+    }
+
+    /// Goes up to the parent directory.
+    pub fn leave_dir(&mut self) -> io::Result<()> {
+        match self.dir.parent().map(Path::to_path_buf) {
+            Some(parent) => self.change_dir(&parent),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Shortest way to write `path` for the file name input: relative to the
+/// working directory when it is inside it.
+pub fn display_path(path: &Path) -> String {
+    let relative = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| cwd.canonicalize().ok())
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf));
+    relative
+        .unwrap_or_else(|| path.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
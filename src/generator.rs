@@ -0,0 +1,305 @@
+use crate::reagent::{Reagent, EXITUS_NAME};
+use crate::solver::replay_path;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+/// Reagent families found in the game.
+pub const FAMILIES: [&str; 5] = ["Echo", "Helicon", "Io", "Ovid", "Solis"];
+
+/// Number of random paths tried before giving up on planting a solution.
+const MAX_ATTEMPTS: usize = 100;
+
+/// First character of a made-up atom code.
+const LETTERS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+/// Second character of a made-up atom code.
+const SYMBOLS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+/// Number of distinct made-up atom codes.
+pub const MAX_ALPHABET_SIZE: usize = LETTERS.len() * SYMBOLS.len();
+
+/// Settings of a generated puzzle.
+#[derive(Clone, Debug)]
+pub struct GeneratorOptions {
+    /// Atoms to build reagents from. Left empty, `alphabet_size` codes are made up.
+    pub alphabet: Vec<String>,
+    pub alphabet_size: usize,
+    pub reagent_count: usize,
+    pub min_atoms: usize,
+    pub max_atoms: usize,
+    /// Chance of each atom after the first one being a negative.
+    pub negative_ratio: f64,
+    pub families: Vec<String>,
+    /// Number of reagents in the planted solution.
+    pub path_length: usize,
+    pub seed: u64,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        Self {
+            alphabet: Vec::new(),
+            alphabet_size: 24,
+            reagent_count: 15,
+            min_atoms: 3,
+            max_atoms: 7,
+            negative_ratio: 0.3,
+            families: FAMILIES.iter().map(|f| f.to_string()).collect(),
+            path_length: 5,
+            seed: 0,
+        }
+    }
+}
+
+/// A random puzzle together with the path its exitus was built from.
+#[derive(Clone, Debug)]
+pub struct GeneratedPuzzle {
+    pub exitus: Reagent,
+    pub reagents: Vec<Reagent>,
+    pub solution: Vec<String>,
+}
+
+impl GeneratedPuzzle {
+    /// The puzzle in the reagent file format, exitus first.
+    pub fn to_file_string(&self) -> String {
+        std::iter::once(&self.exitus)
+            .chain(self.reagents.iter())
+            .map(|r| format!("{}\n", r.to_line()))
+            .collect()
+    }
+
+    /// The planted solution in the `*_solution.txt` format.
+    pub fn solution_file_string(&self) -> String {
+        format!("{}\n\n{:?}\n", self.solution.join(", "), self.solution)
+    }
+}
+
+/// The given alphabet without repeated atoms, in the order given.
+fn distinct_alphabet(alphabet: &[String]) -> Vec<String> {
+    let mut distinct: Vec<String> = Vec::with_capacity(alphabet.len());
+    for atom in alphabet {
+        if !distinct.contains(atom) {
+            distinct.push(atom.clone());
+        }
+    }
+    distinct
+}
+
+fn validate(options: &GeneratorOptions) -> Result<(), String> {
+    let alphabet_len = match distinct_alphabet(&options.alphabet).len() {
+        0 => options.alphabet_size,
+        len => len,
+    };
+
+    // Such atoms would be read back as a negative or as several atoms.
+    if let Some(atom) = options
+        .alphabet
+        .iter()
+        .find(|a| a.is_empty() || a.starts_with('-') || a.contains(char::is_whitespace))
+    {
+        return Err(format!(
+            "alphabet atom '{}' must not be empty, start with '-' or contain spaces",
+            atom
+        ));
+    }
+    if options.families.is_empty() {
+        return Err("at least one reagent family is needed".to_string());
+    }
+    if options.reagent_count < 2 {
+        return Err("at least two reagents are needed".to_string());
+    }
+    if options.min_atoms == 0 || options.min_atoms > options.max_atoms {
+        return Err("atoms per reagent must be a non-empty range above zero".to_string());
+    }
+    if options.alphabet.is_empty() && options.alphabet_size > MAX_ALPHABET_SIZE {
+        return Err(format!(
+            "at most {} atoms can be made up, give an alphabet for more",
+            MAX_ALPHABET_SIZE
+        ));
+    }
+    if alphabet_len < options.max_atoms {
+        return Err(format!(
+            "alphabet of {} atoms is smaller than {} atoms per reagent",
+            alphabet_len, options.max_atoms
+        ));
+    }
+    if options.path_length == 0 {
+        return Err("the planted path needs at least one reagent".to_string());
+    }
+    if !(0.0..=1.0).contains(&options.negative_ratio) {
+        return Err("negative ratio must be between 0 and 1".to_string());
+    }
+
+    Ok(())
+}
+
+fn random_alphabet(rng: &mut StdRng, size: usize) -> Vec<String> {
+    let mut alphabet: Vec<String> = Vec::with_capacity(size);
+    while alphabet.len() < size {
+        let atom = format!(
+            "{}{}",
+            LETTERS[rng.gen_range(0..LETTERS.len())] as char,
+            SYMBOLS[rng.gen_range(0..SYMBOLS.len())] as char
+        );
+        if !alphabet.contains(&atom) {
+            alphabet.push(atom);
+        }
+    }
+
+    alphabet
+}
+
+fn random_reagent(
+    rng: &mut StdRng,
+    name: String,
+    alphabet: &[String],
+    options: &GeneratorOptions,
+) -> Reagent {
+    let count = rng.gen_range(options.min_atoms..=options.max_atoms);
+    let mut positives = Vec::new();
+    let mut negatives = Vec::new();
+
+    for (i, atom) in alphabet.choose_multiple(rng, count).enumerate() {
+        if i > 0 && rng.gen_bool(options.negative_ratio) {
+            negatives.push(format!("-{}", atom));
+        } else {
+            positives.push(atom.clone());
+        }
+    }
+
+    positives.extend(negatives);
+
+    Reagent {
+        name,
+        atoms: positives,
+        score: None,
+    }
+}
+
+fn random_path(rng: &mut StdRng, reagents: &[Reagent], length: usize) -> Vec<String> {
+    let mut path: Vec<String> = Vec::with_capacity(length);
+
+    while path.len() < length {
+        let reagent = &reagents[rng.gen_range(0..reagents.len())];
+        if path.last() != Some(&reagent.name) {
+            path.push(reagent.name.clone());
+        }
+    }
+
+    path
+}
+
+/// Builds a random puzzle with a planted solution.
+///
+/// Reagents are drawn at random, then a random path through them is
+/// replayed and whatever sequence it leaves behind becomes the exitus.
+/// The same options and seed always give the same puzzle.
+pub fn generate(options: &GeneratorOptions) -> Result<GeneratedPuzzle, String> {
+    validate(options)?;
+
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let alphabet = match options.alphabet.len() {
+        0 => random_alphabet(&mut rng, options.alphabet_size),
+        _ => distinct_alphabet(&options.alphabet),
+    };
+
+    let reagents: Vec<Reagent> = (0..options.reagent_count)
+        .map(|i| {
+            let family = &options.families[i % options.families.len()];
+            let name = format!("{}-{}", family, i / options.families.len() + 1);
+            random_reagent(&mut rng, name, &alphabet, options)
+        })
+        .collect();
+
+    for _ in 0..MAX_ATTEMPTS {
+        let solution = random_path(&mut rng, &reagents, options.path_length);
+        let sequence = match replay_path(&reagents, &solution) {
+            Some(combinator) => combinator.sequence,
+            None => continue,
+        };
+
+        if sequence.len() < options.min_atoms {
+            continue;
+        }
+
+        // Group the reagents by family the way the game lists them.
+        let families = options.families.len();
+        let mut order: Vec<usize> = (0..reagents.len()).collect();
+        order.sort_by_key(|i| (i % families, i / families));
+
+        return Ok(GeneratedPuzzle {
+            exitus: Reagent {
+                name: EXITUS_NAME.to_string(),
+                atoms: sequence,
+                score: None,
+            },
+            reagents: order.iter().map(|&i| reagents[i].clone()).collect(),
+            solution,
+        });
+    }
+
+    Err(format!(
+        "no path of {} reagents left at least {} atoms after {} attempts",
+        options.path_length, options.min_atoms, MAX_ATTEMPTS
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reagent::parse_reagents;
+    use crate::solver::is_valid_path;
+
+    fn options(seed: u64) -> GeneratorOptions {
+        GeneratorOptions {
+            seed,
+            ..GeneratorOptions::default()
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_file() {
+        let first = generate(&options(7)).unwrap();
+        let second = generate(&options(7)).unwrap();
+
+        assert_eq!(first.to_file_string(), second.to_file_string());
+        assert_eq!(first.solution_file_string(), second.solution_file_string());
+    }
+
+    #[test]
+    fn planted_path_solves_the_written_puzzle() {
+        for seed in 0..20 {
+            let puzzle = generate(&options(seed)).unwrap();
+            let (exitus, reagents) = parse_reagents(&puzzle.to_file_string());
+            let exitus = exitus.expect("the file starts with the exitus");
+
+            assert_eq!(exitus.name, EXITUS_NAME);
+            assert_eq!(reagents.len(), puzzle.reagents.len());
+            assert!(
+                is_valid_path(&exitus, &reagents, &puzzle.solution),
+                "seed {}: {:?} does not solve the puzzle",
+                seed,
+                puzzle.solution
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_atoms_that_do_not_read_back() {
+        for atom in ["-AB", "A B", ""] {
+            let mut options = options(0);
+            options.alphabet = ["XA", "XB", "XC", "XD", "XE", "XF", "XG", atom]
+                .iter()
+                .map(|a| a.to_string())
+                .collect();
+            assert!(generate(&options).is_err(), "accepted '{}'", atom);
+        }
+    }
+
+    #[test]
+    fn repeated_alphabet_atoms_count_once() {
+        let mut options = options(0);
+        options.alphabet = vec!["AA".to_string(); 10];
+        assert!(generate(&options).is_err());
+    }
+}
@@ -1,162 +1,484 @@
-use crate::app::{App, AppResult, Status};
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crate::app::{App, AppResult};
+use crate::keymap::{Action, Mode};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Rect;
 
-use crate::app::ActiveBlock;
-use crate::reagent::{load_reagents, parse_reagents};
+use crate::app::{ActiveBlock, Setting, Status};
+use crate::palette::{parse_command, Command, Palette};
 
 /// Handles the key events and updates the state of [`App`].
+///
+/// The keymap turns the key into an action for the current mode. In the
+/// text modes a key without an action is typed.
 pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
-    match key_event.code {
-        // Exit application on `ESC` or `q`
-        KeyCode::Esc | KeyCode::Char('q') => {
-            if !app.edit_mode {
-                app.quit();
-            } else {
-                match &app.active_block {
-                    ActiveBlock::FileNameInput => {
-                        // app.file_name_input.push('q')
-                        app.enter_char('q')
+    // Any key closes the help overlay.
+    if app.help.take().is_some() {
+        return Ok(());
+    }
+
+    let mode = app.mode();
+    let action = app.keymap.action(mode, key_event);
+
+    match (mode, action) {
+        (Mode::ConfirmQuit, action) => handle_confirm_quit(action, app),
+        (Mode::ConfirmLoad, action) => handle_confirm_load(action, app),
+        (_, Some(Action::ForceQuit)) => app.quit(),
+        (_, Some(Action::Help)) => app.help = Some(mode),
+        (Mode::Input | Mode::Editor | Mode::Palette, None) => {
+            if let Some(c) = typed_char(key_event) {
+                match mode {
+                    Mode::Editor => {
+                        app.puzzle_mut().reagent_editor.insert_char(c);
+                        app.reparse_editor();
                     }
-                    ActiveBlock::ReagentOutput => {
-                        // app.reagent_string.push('q');
-                        // app.enter_char('q')
+                    Mode::Palette => {
+                        if let Some(palette) = &mut app.palette {
+                            palette.enter_char(c);
+                        }
                     }
+                    _ => app.enter_char(c),
                 }
             }
         }
-        // Exit application on `Ctrl-C`
-        KeyCode::Char('c') | KeyCode::Char('C') => {
-            if key_event.modifiers == KeyModifiers::CONTROL {
+        (_, None) => {}
+        (Mode::Normal, Some(action)) => handle_normal_action(action, app),
+        (Mode::Input, Some(action)) => handle_input_action(action, app),
+        (Mode::Editor, Some(action)) => handle_editor_action(action, app),
+        (Mode::Sandbox, Some(action)) => handle_sandbox_action(action, app),
+        (Mode::Palette, Some(action)) => handle_palette_action(action, app),
+        (Mode::Browser, Some(action)) => handle_browser_action(action, app),
+        (Mode::Recent, Some(action)) => handle_recent_action(action, app),
+        (Mode::Tree, Some(action)) => handle_tree_action(action, app),
+    }
+    Ok(())
+}
+
+/// Character typed by a key press, unless it is a shortcut.
+fn typed_char(key_event: KeyEvent) -> Option<char> {
+    match key_event.code {
+        KeyCode::Char(c)
+            if !key_event
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+        {
+            Some(c)
+        }
+        _ => None,
+    }
+}
+
+/// Answers the unsaved changes prompt; anything but quit or save cancels.
+fn handle_confirm_quit(action: Option<Action>, app: &mut App) {
+    match action {
+        Some(Action::Quit) => app.quit(),
+        Some(Action::Save) => {
+            app.save_all();
+            if !app.puzzles.iter().any(|p| p.dirty) {
                 app.quit();
             }
+            app.confirm_quit = false;
         }
-        KeyCode::Up => match &app.active_block {
-            ActiveBlock::FileNameInput => {
-                // app.switch_active_block(ActiveBlock::ReagentOutput);
-                // app.character_index = app.reagents.len();
-            }
-            ActiveBlock::ReagentOutput => {
-                // app.switch_active_block(ActiveBlock::FileNameInput);
-                // app.character_index = app.file_name_input.len();
-            }
-        },
-        KeyCode::Down => match &app.active_block {
-            ActiveBlock::FileNameInput => {
-                // app.switch_active_block(ActiveBlock::ReagentOutput);
-                // app.character_index = app.reagents.len() - 1;
+        _ => app.confirm_quit = false,
+    }
+}
+
+/// Answers the reload over unsaved changes prompt; anything but load or
+/// save cancels.
+fn handle_confirm_load(action: Option<Action>, app: &mut App) {
+    match action {
+        Some(Action::Load) => app.load_reagents_file(),
+        Some(Action::Save) => {
+            app.save();
+            if !app.puzzle().dirty {
+                app.load_reagents_file();
             }
-            ActiveBlock::ReagentOutput => {
-                // app.switch_active_block(ActiveBlock::FileNameInput);
-                // app.character_index = app.file_name_input.len() - 1;
+        }
+        _ => {}
+    }
+    app.confirm_load = false;
+}
+
+fn handle_normal_action(action: Action, app: &mut App) {
+    match action {
+        Action::Quit => app.request_quit(),
+        Action::FocusNext => app.focus_next(),
+        Action::FocusPrevious => app.focus_previous(),
+        Action::Load => app.request_load(),
+        Action::Solve => {
+            if app.puzzle().reagents.is_empty() {
+                app.puzzle_mut().solver_log = vec!["No reagents loaded".to_string()];
+            } else {
+                app.find_solutions();
             }
-        },
-        KeyCode::Left => {
-            if app.edit_mode {
-                match &app.active_block {
-                    ActiveBlock::FileNameInput => app.move_cursor_left(),
-                    ActiveBlock::ReagentOutput => {
-                        // app.character_index = app.character_index.saturating_sub(1);
-                    }
-                }
+        }
+        Action::NextTab => app.next_solver_tab(),
+        Action::PreviousTab => app.previous_solver_tab(),
+        Action::PageUp => app.scroll_solver_log_up(10),
+        Action::PageDown => app.scroll_solver_log_down(10),
+        Action::ToggleSandbox => app.toggle_sandbox(),
+        Action::Save => app.save(),
+        Action::SaveAs => app.save_as(),
+        Action::Help => app.help = Some(Mode::Normal),
+        Action::CommandPalette => app.palette = Some(Palette::default()),
+        Action::Browse => app.open_file_browser(),
+        Action::ToggleWatch => app.cycle_watch(),
+        Action::NextPuzzle => app.next_puzzle(),
+        Action::PreviousPuzzle => app.previous_puzzle(),
+        Action::NewPuzzle => app.new_puzzle(),
+        Action::ClosePuzzle => app.close_puzzle(),
+        Action::Recent => app.open_recent(),
+        Action::CycleTheme => app.set_theme(app.theme.next()),
+        Action::CopySolution => app.copy_solution(None),
+        Action::SearchTree => app.open_search_tree(),
+        action => handle_pane_action(action, app),
+    }
+}
+
+/// Handles the actions specific to the focused pane.
+///
+/// Up and Down move within the reagent, settings and solver log panes and
+/// move focus on once they run past either end.
+fn handle_pane_action(action: Action, app: &mut App) {
+    match (app.active_block, action) {
+        (ActiveBlock::FileNameInput | ActiveBlock::ReagentOutput, Action::Confirm) => {
+            app.edit_mode = true;
+        }
+
+        (ActiveBlock::ReagentOutput, Action::Up) if app.puzzle().selected_reagent > 0 => {
+            app.select_previous_reagent();
+        }
+        (ActiveBlock::ReagentOutput, Action::Down)
+            if app.puzzle().selected_reagent + 1 < app.puzzle().reagents.len() =>
+        {
+            app.select_next_reagent();
+        }
+
+        (ActiveBlock::Settings, Action::Up) if app.selected_setting > 0 => {
+            app.select_setting(app.selected_setting - 1);
+        }
+        (ActiveBlock::Settings, Action::Down) if app.selected_setting + 1 < Setting::ALL.len() => {
+            app.select_setting(app.selected_setting + 1);
+        }
+        (ActiveBlock::Settings, Action::Left | Action::Decrease) => app.adjust_setting(false),
+        (ActiveBlock::Settings, Action::Right | Action::Confirm | Action::Increase) => {
+            app.adjust_setting(true);
+        }
+
+        (ActiveBlock::SolverLog, Action::Up) if app.puzzle().solver_scroll > 0 => {
+            app.scroll_solver_log_up(1);
+        }
+        (ActiveBlock::SolverLog, Action::Down) if !app.solver_log_at_end() => {
+            app.scroll_solver_log_down(1);
+        }
+        (ActiveBlock::SolverLog, Action::Left) => app.previous_solver_tab(),
+        (ActiveBlock::SolverLog, Action::Right) => app.next_solver_tab(),
+
+        (_, Action::Up) => app.focus_previous(),
+        (_, Action::Down) => app.focus_next(),
+        _ => {}
+    }
+}
+
+/// Handles the actions while the file name is being edited.
+fn handle_input_action(action: Action, app: &mut App) {
+    match action {
+        Action::Confirm | Action::Cancel => app.edit_mode = false,
+        Action::Complete => app.complete_file_name(),
+        Action::Left => app.move_cursor_left(),
+        Action::Right => app.move_cursor_right(),
+        Action::Home => app.character_index = 0,
+        Action::End => app.character_index = app.clamp_cursor(usize::MAX),
+        Action::DeleteBackward => app.delete_char(),
+        _ => {}
+    }
+}
+
+/// Handles the actions while the reagent pane is being edited.
+///
+/// Every change is re-parsed right away so the log shows whether the
+/// reagents are still valid.
+fn handle_editor_action(action: Action, app: &mut App) {
+    let editor = &mut app.puzzle_mut().reagent_editor;
+
+    match action {
+        Action::Cancel => app.edit_mode = false,
+        Action::Left => editor.move_left(),
+        Action::Right => editor.move_right(),
+        Action::Up => editor.move_up(),
+        Action::Down => editor.move_down(),
+        Action::Home => editor.move_home(),
+        Action::End => editor.move_end(),
+        action => {
+            match action {
+                Action::DeleteAtom => editor.delete_atom(),
+                Action::DeleteLine => editor.delete_line(),
+                Action::Newline => editor.insert_newline(),
+                Action::DeleteBackward => editor.delete_char(),
+                Action::DeleteForward => editor.delete_char_forward(),
+                _ => return,
             }
+            app.reparse_editor();
         }
-        KeyCode::Right => {
-            if app.edit_mode {
-                match &app.active_block {
-                    ActiveBlock::FileNameInput => {
-                        app.move_cursor_right();
-                    }
-                    ActiveBlock::ReagentOutput => {
-                        // app.character_index = app.character_index.saturating_add(1);
-                    }
-                }
+    }
+}
+
+/// Handles the actions while the sandbox is open.
+fn handle_sandbox_action(action: Action, app: &mut App) {
+    let Some(sandbox) = &mut app.puzzle_mut().sandbox else {
+        return;
+    };
+
+    match action {
+        Action::Cancel | Action::ToggleSandbox => app.puzzle_mut().sandbox = None,
+        Action::Up => app.select_previous_reagent(),
+        Action::Down => app.select_next_reagent(),
+        Action::Confirm => app.sandbox_apply(),
+        Action::Undo => sandbox.undo(),
+        Action::Finish => app.sandbox_finish(),
+        _ => {}
+    }
+}
+
+/// Handles the actions while a command is typed in the palette.
+fn handle_palette_action(action: Action, app: &mut App) {
+    let Some(palette) = &mut app.palette else {
+        return;
+    };
+
+    match action {
+        Action::Cancel => app.palette = None,
+        Action::Complete => palette.complete(),
+        Action::DeleteBackward => palette.delete_char(),
+        Action::Confirm => {
+            let input = palette.input.clone();
+            app.palette = None;
+            run_command(&input, app);
+        }
+        _ => {}
+    }
+}
+
+/// Handles the actions while the file browser is open.
+fn handle_browser_action(action: Action, app: &mut App) {
+    let Some(browser) = &mut app.file_browser else {
+        return;
+    };
+
+    match action {
+        Action::Cancel => app.file_browser = None,
+        Action::Up => browser.select_previous(),
+        Action::Down => browser.select_next(),
+        Action::Left => {
+            if let Err(e) = browser.leave_dir() {
+                app.status = Status::Error;
+                app.log_message = format!("Error listing files: {}", e);
             }
         }
-        KeyCode::Char('r') => {
-            if !app.edit_mode {
-                match load_reagents(&app.file_name_input) {
-                    Ok(contents) => {
-                        let (exitus, reagents) = parse_reagents(&contents);
-                        match exitus {
-                            Some(e) => {
-                                app.exitus = e;
-                            }
-                            None => {
-                                app.status = Status::Error;
-                                app.log_message =
-                                    format!("No exitus found in {}", app.file_name_input);
-                                return Ok(());
-                            }
-                        };
-                        app.reagents = reagents;
-                        app.status = Status::Ok;
-                        app.log_message = format!("Loaded {} reagents", app.reagents.len());
-                    }
-                    Err(e) => {
-                        app.status = Status::Error;
-                        app.log_message = format!("Error loading reagents: {}", e);
-                    }
+        Action::Right if browser.selected_entry().is_some_and(|e| e.is_dir) => {
+            app.browser_open_selected();
+        }
+        Action::Confirm => app.browser_open_selected(),
+        _ => {}
+    }
+}
+
+/// Handles the actions while the recent files popup is open.
+fn handle_recent_action(action: Action, app: &mut App) {
+    let Some(selected) = app.recent else {
+        return;
+    };
+
+    match action {
+        Action::Cancel => app.recent = None,
+        Action::Up => app.select_recent(selected.saturating_sub(1)),
+        Action::Down => app.select_recent(selected + 1),
+        Action::Confirm => app.recent_open_selected(),
+        _ => {}
+    }
+}
+
+/// Handles the actions while the search tree explorer is open.
+fn handle_tree_action(action: Action, app: &mut App) {
+    let Some(explorer) = &mut app.search_tree else {
+        return;
+    };
+
+    match action {
+        Action::Cancel => app.search_tree = None,
+        Action::Up => explorer.select(explorer.selected.saturating_sub(1)),
+        Action::Down => explorer.select(explorer.selected + 1),
+        Action::Left => explorer.collapse(),
+        Action::Right => explorer.expand(),
+        Action::Confirm => explorer.toggle(),
+        Action::Diverge => app.search_tree_jump_to_divergence(),
+        Action::NextTab => app.search_tree_cycle_start(true),
+        Action::PreviousTab => app.search_tree_cycle_start(false),
+        _ => {}
+    }
+}
+
+/// Runs a palette command, reporting mistakes in the log.
+fn run_command(input: &str, app: &mut App) {
+    let command = match parse_command(input) {
+        Ok(command) => command,
+        Err(e) => {
+            app.status = Status::Error;
+            app.log_message = e;
+            return;
+        }
+    };
+
+    match command {
+        Command::Run(action) => handle_normal_action(action, app),
+        Command::Open(file_name) => app.open_file(&file_name),
+        Command::Set(setting, value) => match app.set_setting(setting, &value) {
+            Ok(_) => {
+                app.status = Status::Ok;
+                app.log_message = format!(
+                    "{} set to {}",
+                    setting.label(),
+                    setting.value(&app.solver_options)
+                );
+            }
+            Err(e) => {
+                app.status = Status::Error;
+                app.log_message = e;
+            }
+        },
+        Command::Export(format, file_name) => app.export_report(format, file_name),
+        Command::Watch(watch) => app.set_watch(watch),
+        Command::Theme(theme) => app.set_theme(theme),
+        Command::Copy(format) => app.copy_solution(format),
+    }
+}
+
+/// Lines scrolled by one step of the mouse wheel.
+const WHEEL_SCROLL_LINES: usize = 3;
+
+fn contains(area: Rect, column: u16, row: u16) -> bool {
+    (area.left()..area.right()).contains(&column) && (area.top()..area.bottom()).contains(&row)
+}
+
+/// Handles the mouse events and updates the state of [`App`].
+pub fn handle_mouse_events(mouse_event: MouseEvent, app: &mut App) -> AppResult<()> {
+    // Popups take no mouse input.
+    if app.help.is_some()
+        || matches!(
+            app.mode(),
+            Mode::ConfirmQuit
+                | Mode::ConfirmLoad
+                | Mode::Palette
+                | Mode::Browser
+                | Mode::Recent
+                | Mode::Tree
+        )
+    {
+        return Ok(());
+    }
+
+    let (column, row) = (mouse_event.column, mouse_event.row);
+    let areas = app.areas;
+    let editing_reagents = app.edit_mode && app.active_block == ActiveBlock::ReagentOutput;
+
+    match mouse_event.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            if contains(areas.puzzle_tabs, column, row) {
+                if let Some(index) = app.puzzle_tab_at_column(column) {
+                    app.select_puzzle(index);
                 }
-            } else {
-                match &app.active_block {
-                    ActiveBlock::FileNameInput => {
-                        // app.file_name_input.push('r');
-                        app.enter_char('r');
-                    }
-                    ActiveBlock::ReagentOutput => {}
+            } else if contains(areas.file_name_input, column, row) {
+                if app.active_block != ActiveBlock::FileNameInput {
+                    app.focus(ActiveBlock::FileNameInput);
+                } else if app.edit_mode {
+                    let index = column.saturating_sub(areas.file_name_text.x) as usize;
+                    app.character_index = app.clamp_cursor(app.file_name_scroll + index);
                 }
-            }
-        }
-        KeyCode::Char('s') => {
-            if !app.edit_mode {
-                if app.reagents.is_empty() {
-                    app.solver_log.clear();
-                    app.solver_log.push("No reagents loaded".to_string());
-                    return Ok(());
+            } else if contains(areas.reagents, column, row) {
+                if editing_reagents {
+                    let editor = &mut app.puzzle_mut().reagent_editor;
+                    editor.move_to(
+                        editor.scroll + (row - areas.reagents.y) as usize,
+                        (column - areas.reagents.x) as usize,
+                    );
                 } else {
-                    app.find_solutions();
-                }
-            } else {
-                match &app.active_block {
-                    ActiveBlock::FileNameInput => {
-                        // app.file_name_input.push('s');
-                        app.enter_char('s')
+                    if app.active_block != ActiveBlock::ReagentOutput {
+                        app.focus(ActiveBlock::ReagentOutput);
+                    }
+                    if let Some(index) = app.reagent_at_row(row) {
+                        app.select_reagent(index);
                     }
-                    ActiveBlock::ReagentOutput => {}
                 }
-            }
-        }
-        KeyCode::Char(val) => {
-            if app.edit_mode {
-                match &app.active_block {
-                    ActiveBlock::FileNameInput => {
-                        // app.file_name_input.push(val);
-                        app.enter_char(val);
+            } else if contains(areas.settings, column, row) {
+                app.focus(ActiveBlock::Settings);
+                if let Some(index) = row.checked_sub(areas.settings.y) {
+                    app.select_setting(index as usize);
+                }
+            } else if contains(areas.log, column, row) {
+                app.focus(ActiveBlock::Log);
+            } else if app.puzzle().sandbox.is_none() && contains(areas.solver_log, column, row) {
+                app.focus(ActiveBlock::SolverLog);
+                if contains(areas.solver_tabs, column, row) {
+                    if let Some(tab) = app.solver_tab_at_column(column) {
+                        app.select_solver_tab(tab);
                     }
-                    ActiveBlock::ReagentOutput => {
-                        // app.reagent_string.push(val);
+                } else if app.puzzle().solver_tab == 0
+                    && contains(areas.solver_content, column, row)
+                {
+                    // Clicking a found path in the overview opens its trace.
+                    if let Some(tab) = app.solution_tab_at_row(row) {
+                        app.select_solver_tab(tab);
                     }
                 }
             }
         }
-        KeyCode::Enter => {
-            app.edit_mode = !app.edit_mode;
-        }
-        KeyCode::Backspace => match &app.active_block {
-            ActiveBlock::FileNameInput => {
-                if app.edit_mode {
-                    // app.file_name_input.pop();
-                    app.delete_char();
-                }
+        MouseEventKind::ScrollUp => {
+            if contains(areas.reagents, column, row) && !editing_reagents {
+                app.scroll_reagents_up(WHEEL_SCROLL_LINES);
+            } else if contains(areas.solver_log, column, row) {
+                app.scroll_solver_log_up(WHEEL_SCROLL_LINES);
             }
-            ActiveBlock::ReagentOutput => {
-                // if app.edit_mode {
-                //     app.reagent_string.pop();
-                // }
+        }
+        MouseEventKind::ScrollDown => {
+            if contains(areas.reagents, column, row) && !editing_reagents {
+                app.scroll_reagents_down(WHEEL_SCROLL_LINES);
+            } else if contains(areas.solver_log, column, row) {
+                app.scroll_solver_log_down(WHEEL_SCROLL_LINES);
             }
-        },
-        // Other handlers you could add here.
+        }
         _ => {}
     }
     Ok(())
 }
+
+/// Handles pasted text and updates the state of [`App`].
+///
+/// Text pasted while editing the reagent pane is inserted at its cursor, and
+/// the first line of it goes into the command palette while that is open.
+/// Otherwise a single line goes into the file name field while it is being
+/// edited, and several lines are read as a whole reagent list.
+pub fn handle_paste_event(text: &str, app: &mut App) -> AppResult<()> {
+    if let Some(palette) = &mut app.palette {
+        let line = text.lines().next().unwrap_or_default();
+        line.chars().for_each(|c| palette.enter_char(c));
+        return Ok(());
+    }
+
+    if app.edit_mode {
+        if let ActiveBlock::ReagentOutput = app.active_block {
+            app.puzzle_mut().reagent_editor.insert_str(text);
+            app.reparse_editor();
+            return Ok(());
+        }
+    }
+
+    let text = text.trim();
+
+    if text.lines().count() > 1 {
+        app.load_pasted_reagents(text);
+    } else if app.edit_mode {
+        if let ActiveBlock::FileNameInput = app.active_block {
+            app.enter_str(text);
+        }
+    }
+    Ok(())
+}
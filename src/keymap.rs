@@ -0,0 +1,789 @@
+// Module: keymap
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::path::PathBuf;
+use std::{env, fmt, fs, io};
+
+/// Name of the keymap file inside the config directory.
+pub const KEYMAP_FILE_NAME: &str = "keys.conf";
+
+/// Something the user can ask for, independent of the key it is bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    ForceQuit,
+    Load,
+    Solve,
+    Save,
+    SaveAs,
+    ToggleSandbox,
+    NextTab,
+    PreviousTab,
+    PageUp,
+    PageDown,
+    FocusNext,
+    FocusPrevious,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    Confirm,
+    Cancel,
+    Increase,
+    Decrease,
+    Newline,
+    DeleteBackward,
+    DeleteForward,
+    DeleteAtom,
+    DeleteLine,
+    Undo,
+    Finish,
+    Help,
+    CommandPalette,
+    Complete,
+    Browse,
+    ToggleWatch,
+    NextPuzzle,
+    PreviousPuzzle,
+    NewPuzzle,
+    ClosePuzzle,
+    Recent,
+    CycleTheme,
+    CopySolution,
+    SearchTree,
+    Diverge,
+}
+
+impl Action {
+    pub const ALL: [Action; 44] = [
+        Action::Quit,
+        Action::ForceQuit,
+        Action::Load,
+        Action::Solve,
+        Action::Save,
+        Action::SaveAs,
+        Action::ToggleSandbox,
+        Action::NextTab,
+        Action::PreviousTab,
+        Action::PageUp,
+        Action::PageDown,
+        Action::FocusNext,
+        Action::FocusPrevious,
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::Home,
+        Action::End,
+        Action::Confirm,
+        Action::Cancel,
+        Action::Increase,
+        Action::Decrease,
+        Action::Newline,
+        Action::DeleteBackward,
+        Action::DeleteForward,
+        Action::DeleteAtom,
+        Action::DeleteLine,
+        Action::Undo,
+        Action::Finish,
+        Action::Help,
+        Action::CommandPalette,
+        Action::Complete,
+        Action::Browse,
+        Action::ToggleWatch,
+        Action::NextPuzzle,
+        Action::PreviousPuzzle,
+        Action::NewPuzzle,
+        Action::ClosePuzzle,
+        Action::Recent,
+        Action::CycleTheme,
+        Action::CopySolution,
+        Action::SearchTree,
+        Action::Diverge,
+    ];
+
+    /// Name used for the action in the keymap file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::ForceQuit => "force-quit",
+            Action::Load => "load",
+            Action::Solve => "solve",
+            Action::Save => "save",
+            Action::SaveAs => "save-as",
+            Action::ToggleSandbox => "sandbox",
+            Action::NextTab => "next-tab",
+            Action::PreviousTab => "previous-tab",
+            Action::PageUp => "page-up",
+            Action::PageDown => "page-down",
+            Action::FocusNext => "focus-next",
+            Action::FocusPrevious => "focus-previous",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Home => "home",
+            Action::End => "end",
+            Action::Confirm => "confirm",
+            Action::Cancel => "cancel",
+            Action::Increase => "increase",
+            Action::Decrease => "decrease",
+            Action::Newline => "newline",
+            Action::DeleteBackward => "delete-backward",
+            Action::DeleteForward => "delete-forward",
+            Action::DeleteAtom => "delete-atom",
+            Action::DeleteLine => "delete-line",
+            Action::Undo => "undo",
+            Action::Finish => "finish",
+            Action::Help => "help",
+            Action::CommandPalette => "command-palette",
+            Action::Complete => "complete",
+            Action::Browse => "browse",
+            Action::ToggleWatch => "toggle-watch",
+            Action::NextPuzzle => "next-puzzle",
+            Action::PreviousPuzzle => "previous-puzzle",
+            Action::NewPuzzle => "new-puzzle",
+            Action::ClosePuzzle => "close-puzzle",
+            Action::Recent => "recent",
+            Action::CycleTheme => "cycle-theme",
+            Action::CopySolution => "copy",
+            Action::SearchTree => "search-tree",
+            Action::Diverge => "diverge",
+        }
+    }
+
+    /// What the action does, for the help overlay.
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::ForceQuit => "Quit right away, even with unsaved changes",
+            Action::Load => "Read the puzzle file",
+            Action::Solve => "Solve the puzzle",
+            Action::Save => "Save the puzzle",
+            Action::SaveAs => "Save the puzzle as the typed file name",
+            Action::ToggleSandbox => "Open or close the sandbox",
+            Action::NextTab => "Next solver log tab",
+            Action::PreviousTab => "Previous solver log tab",
+            Action::PageUp => "Scroll the solver log up",
+            Action::PageDown => "Scroll the solver log down",
+            Action::FocusNext => "Focus the next pane",
+            Action::FocusPrevious => "Focus the previous pane",
+            Action::Up => "Move up",
+            Action::Down => "Move down",
+            Action::Left => "Move left",
+            Action::Right => "Move right",
+            Action::Home => "Go to the start of the line",
+            Action::End => "Go to the end of the line",
+            Action::Confirm => "Edit, apply or confirm",
+            Action::Cancel => "Stop editing or close",
+            Action::Increase => "Increase the selected setting",
+            Action::Decrease => "Decrease the selected setting",
+            Action::Newline => "Start a new line",
+            Action::DeleteBackward => "Delete the character before the cursor",
+            Action::DeleteForward => "Delete the character under the cursor",
+            Action::DeleteAtom => "Delete the atom before the cursor",
+            Action::DeleteLine => "Delete the reagent",
+            Action::Undo => "Undo the last reagent",
+            Action::Finish => "Finish the path with the solver",
+            Action::Help => "Show the keys",
+            Action::CommandPalette => "Open the command palette",
+            Action::Complete => "Complete the command or path",
+            Action::Browse => "Browse for a puzzle file",
+            Action::ToggleWatch => "Watch the file: off, reload or reload and solve",
+            Action::NextPuzzle => "Next puzzle tab",
+            Action::PreviousPuzzle => "Previous puzzle tab",
+            Action::NewPuzzle => "Open an empty puzzle tab",
+            Action::ClosePuzzle => "Close the puzzle tab",
+            Action::Recent => "Reopen a recent file",
+            Action::CycleTheme => "Switch to the next colour theme",
+            Action::CopySolution => "Copy the solution shown, or the shortest",
+            Action::SearchTree => "Explore the recorded search tree",
+            Action::Diverge => "Jump to where the branch left the exitus",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+}
+
+/// Input mode, deciding which bindings apply.
+///
+/// In the text modes keys without a binding are typed as text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Moving between panes and running commands.
+    Normal,
+    /// Typing the file name.
+    Input,
+    /// Editing the reagent pane.
+    Editor,
+    /// Applying reagents in the sandbox.
+    Sandbox,
+    /// Answering the unsaved changes prompt.
+    ConfirmQuit,
+    /// Answering whether to reload over unsaved changes.
+    ConfirmLoad,
+    /// Typing a command.
+    Palette,
+    /// Picking a file in the file browser.
+    Browser,
+    /// Picking a file in the recent files list.
+    Recent,
+    /// Exploring a recorded search tree.
+    Tree,
+}
+
+impl Mode {
+    const ALL: [Mode; 10] = [
+        Mode::Normal,
+        Mode::Input,
+        Mode::Editor,
+        Mode::Sandbox,
+        Mode::ConfirmQuit,
+        Mode::ConfirmLoad,
+        Mode::Palette,
+        Mode::Browser,
+        Mode::Recent,
+        Mode::Tree,
+    ];
+
+    /// Section name of the mode in the keymap file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Normal => "normal",
+            Mode::Input => "input",
+            Mode::Editor => "editor",
+            Mode::Sandbox => "sandbox",
+            Mode::ConfirmQuit => "confirm-quit",
+            Mode::ConfirmLoad => "confirm-load",
+            Mode::Palette => "palette",
+            Mode::Browser => "browser",
+            Mode::Recent => "recent",
+            Mode::Tree => "tree",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.name() == name)
+    }
+}
+
+/// A key with its modifiers, as written in the keymap file: `q`, `ctrl-c`,
+/// `shift-tab`, `pgdn`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }.normalized()
+    }
+
+    /// Drops the shift terminals report along with upper case letters and
+    /// back tab, so `A` matches whether or not shift was reported. Control
+    /// letters are case insensitive.
+    fn normalized(mut self) -> Self {
+        if let KeyCode::Char(_) | KeyCode::BackTab = self.code {
+            self.modifiers.remove(KeyModifiers::SHIFT);
+        }
+        if let KeyCode::Char(c) = self.code {
+            if self.modifiers.contains(KeyModifiers::CONTROL) {
+                self.code = KeyCode::Char(c.to_ascii_lowercase());
+            }
+        }
+        self
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(key_event: KeyEvent) -> Self {
+        Key::new(key_event.code, key_event.modifiers)
+    }
+}
+
+impl std::str::FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+
+        // Keys are shown as `^c`, which reads back as `ctrl-c`.
+        if let Some(tail) = rest.strip_prefix('^').filter(|t| !t.is_empty()) {
+            modifiers |= KeyModifiers::CONTROL;
+            rest = tail;
+        }
+
+        // A lone `-` is a key, anything before a dash is a modifier.
+        while let Some((modifier, tail)) = rest.split_once('-').filter(|(_, t)| !t.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier '{}' in '{}'", modifier, s)),
+            };
+            rest = tail;
+        }
+
+        let code = match rest.to_lowercase().as_str() {
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pgup" => KeyCode::PageUp,
+            "pgdn" => KeyCode::PageDown,
+            "space" => KeyCode::Char(' '),
+            name => match (rest.chars().next(), rest.chars().count()) {
+                (Some(c), 1) => KeyCode::Char(c),
+                _ => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n) => KeyCode::F(n),
+                    None => return Err(format!("unknown key '{}'", s)),
+                },
+            },
+        };
+
+        Ok(Key::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "^")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift-")?;
+        }
+        match self.code {
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::BackTab => write!(f, "shift-tab"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "del"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pgup"),
+            KeyCode::PageDown => write!(f, "pgdn"),
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// Default bindings per mode, in the keymap file syntax.
+const DEFAULT_BINDINGS: &[(Mode, Action, &str)] = &[
+    (Mode::Normal, Action::Quit, "q, esc"),
+    (Mode::Normal, Action::ForceQuit, "ctrl-c"),
+    (Mode::Normal, Action::Confirm, "enter"),
+    (Mode::Normal, Action::Solve, "s"),
+    (Mode::Normal, Action::Load, "r"),
+    (Mode::Normal, Action::Save, "w"),
+    (Mode::Normal, Action::SaveAs, "a"),
+    (Mode::Normal, Action::ToggleSandbox, "p"),
+    (Mode::Normal, Action::FocusNext, "tab"),
+    (Mode::Normal, Action::FocusPrevious, "shift-tab"),
+    (Mode::Normal, Action::PreviousTab, "["),
+    (Mode::Normal, Action::NextTab, "]"),
+    (Mode::Normal, Action::PageUp, "pgup"),
+    (Mode::Normal, Action::PageDown, "pgdn"),
+    (Mode::Normal, Action::Up, "up"),
+    (Mode::Normal, Action::Down, "down"),
+    (Mode::Normal, Action::Left, "left"),
+    (Mode::Normal, Action::Right, "right"),
+    (Mode::Normal, Action::Increase, "+"),
+    (Mode::Normal, Action::Decrease, "-"),
+    (Mode::Normal, Action::Help, "?, f1"),
+    (Mode::Normal, Action::CommandPalette, ":"),
+    (Mode::Normal, Action::Browse, "o"),
+    (Mode::Normal, Action::ToggleWatch, "W"),
+    (Mode::Normal, Action::PreviousPuzzle, "{"),
+    (Mode::Normal, Action::NextPuzzle, "}"),
+    (Mode::Normal, Action::NewPuzzle, "n"),
+    (Mode::Normal, Action::ClosePuzzle, "x"),
+    (Mode::Normal, Action::Recent, "R"),
+    (Mode::Normal, Action::CycleTheme, "T"),
+    (Mode::Normal, Action::CopySolution, "y"),
+    (Mode::Normal, Action::SearchTree, "t"),
+    (Mode::Input, Action::Confirm, "enter"),
+    (Mode::Input, Action::Cancel, "esc"),
+    (Mode::Input, Action::ForceQuit, "ctrl-c"),
+    (Mode::Input, Action::Complete, "tab"),
+    (Mode::Input, Action::Left, "left"),
+    (Mode::Input, Action::Right, "right"),
+    (Mode::Input, Action::Home, "home"),
+    (Mode::Input, Action::End, "end"),
+    (Mode::Input, Action::DeleteBackward, "backspace"),
+    (Mode::Input, Action::Help, "f1"),
+    (Mode::Editor, Action::Cancel, "esc"),
+    (Mode::Editor, Action::ForceQuit, "ctrl-c"),
+    (Mode::Editor, Action::DeleteAtom, "ctrl-w"),
+    (Mode::Editor, Action::DeleteLine, "ctrl-k"),
+    (Mode::Editor, Action::Newline, "enter"),
+    (Mode::Editor, Action::DeleteBackward, "backspace"),
+    (Mode::Editor, Action::DeleteForward, "del"),
+    (Mode::Editor, Action::Up, "up"),
+    (Mode::Editor, Action::Down, "down"),
+    (Mode::Editor, Action::Left, "left"),
+    (Mode::Editor, Action::Right, "right"),
+    (Mode::Editor, Action::Home, "home"),
+    (Mode::Editor, Action::End, "end"),
+    (Mode::Editor, Action::Help, "f1"),
+    (Mode::Sandbox, Action::Confirm, "enter, space"),
+    (Mode::Sandbox, Action::Undo, "u, backspace"),
+    (Mode::Sandbox, Action::Finish, "f"),
+    (Mode::Sandbox, Action::Cancel, "esc"),
+    (Mode::Sandbox, Action::ToggleSandbox, "p"),
+    (Mode::Sandbox, Action::ForceQuit, "ctrl-c"),
+    (Mode::Sandbox, Action::Up, "up, k"),
+    (Mode::Sandbox, Action::Down, "down, j"),
+    (Mode::Sandbox, Action::Help, "?, f1"),
+    (Mode::ConfirmQuit, Action::Quit, "y"),
+    (Mode::ConfirmQuit, Action::Save, "w"),
+    (Mode::ConfirmLoad, Action::Load, "y"),
+    (Mode::ConfirmLoad, Action::Save, "w"),
+    (Mode::Palette, Action::Confirm, "enter"),
+    (Mode::Palette, Action::Cancel, "esc"),
+    (Mode::Palette, Action::Complete, "tab"),
+    (Mode::Palette, Action::DeleteBackward, "backspace"),
+    (Mode::Palette, Action::ForceQuit, "ctrl-c"),
+    (Mode::Palette, Action::Help, "f1"),
+    (Mode::Browser, Action::Up, "up, k"),
+    (Mode::Browser, Action::Down, "down, j"),
+    (Mode::Browser, Action::Left, "left, backspace, h"),
+    (Mode::Browser, Action::Right, "right, l"),
+    (Mode::Browser, Action::Confirm, "enter"),
+    (Mode::Browser, Action::Cancel, "esc, o"),
+    (Mode::Browser, Action::ForceQuit, "ctrl-c"),
+    (Mode::Browser, Action::Help, "?, f1"),
+    (Mode::Recent, Action::Up, "up, k"),
+    (Mode::Recent, Action::Down, "down, j"),
+    (Mode::Recent, Action::Confirm, "enter"),
+    (Mode::Recent, Action::Cancel, "esc, R"),
+    (Mode::Recent, Action::ForceQuit, "ctrl-c"),
+    (Mode::Recent, Action::Help, "?, f1"),
+    (Mode::Tree, Action::Up, "up, k"),
+    (Mode::Tree, Action::Down, "down, j"),
+    (Mode::Tree, Action::Left, "left, h"),
+    (Mode::Tree, Action::Right, "right, l"),
+    (Mode::Tree, Action::Confirm, "enter, space"),
+    (Mode::Tree, Action::Diverge, "d"),
+    (Mode::Tree, Action::PreviousTab, "["),
+    (Mode::Tree, Action::NextTab, "]"),
+    (Mode::Tree, Action::Cancel, "esc, t"),
+    (Mode::Tree, Action::ForceQuit, "ctrl-c"),
+    (Mode::Tree, Action::Help, "?, f1"),
+];
+
+/// Maps key presses to actions, per mode.
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(Mode, Key, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Self {
+            bindings: Vec::new(),
+        };
+        for &(mode, action, keys) in DEFAULT_BINDINGS {
+            keymap
+                .bind(mode, action, keys)
+                .expect("default bindings are valid");
+        }
+        keymap
+    }
+}
+
+impl Keymap {
+    /// Action bound to a key press in `mode`, if any.
+    pub fn action(&self, mode: Mode, key_event: KeyEvent) -> Option<Action> {
+        let key = Key::from(key_event);
+        self.bindings
+            .iter()
+            .find(|&&(m, k, _)| m == mode && k == key)
+            .map(|&(_, _, action)| action)
+    }
+
+    /// Keys bound to `action` in `mode`, in the order they were bound.
+    pub fn keys(&self, mode: Mode, action: Action) -> Vec<Key> {
+        self.bindings
+            .iter()
+            .filter(|&&(m, _, a)| m == mode && a == action)
+            .map(|&(_, key, _)| key)
+            .collect()
+    }
+
+    /// Every action bound in `mode` with its keys, for the help overlay.
+    pub fn mode_bindings(&self, mode: Mode) -> Vec<(Action, Vec<Key>)> {
+        Action::ALL
+            .into_iter()
+            .map(|action| (action, self.keys(mode, action)))
+            .filter(|(_, keys)| !keys.is_empty())
+            .collect()
+    }
+
+    /// Replaces the keys of `action` in `mode` with a comma separated list.
+    ///
+    /// A key can only do one thing per mode, so it is unbound from whatever
+    /// it did before.
+    fn bind(&mut self, mode: Mode, action: Action, keys: &str) -> Result<(), String> {
+        let keys = keys
+            .split(',')
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<Key>, String>>()?;
+
+        self.bindings
+            .retain(|&(m, k, a)| m != mode || (a != action && !keys.contains(&k)));
+        self.bindings
+            .extend(keys.into_iter().map(|key| (mode, key, action)));
+        Ok(())
+    }
+
+    /// Applies a keymap file on top of the current bindings.
+    ///
+    /// The file is split into `[mode]` sections of `action = key, key` lines.
+    /// Errors name the line they were found on.
+    pub fn apply_config(&mut self, contents: &str) -> Result<(), String> {
+        let mut mode = Mode::Normal;
+
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            let error = |e: String| format!("line {}: {}", i + 1, e);
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                mode = Mode::from_name(section.trim())
+                    .ok_or_else(|| error(format!("unknown mode '{}'", section)))?;
+                continue;
+            }
+
+            let (action, keys) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected 'action = keys'".to_string()))?;
+            let action = Action::from_name(action.trim())
+                .ok_or_else(|| error(format!("unknown action '{}'", action.trim())))?;
+            self.bind(mode, action, keys).map_err(error)?;
+        }
+
+        Ok(())
+    }
+
+    /// Loads the default keymap with the user's keymap file applied on top.
+    ///
+    /// A missing file is not an error; a broken one leaves the defaults in
+    /// place and says what is wrong.
+    pub fn load() -> (Self, Option<String>) {
+        let keymap = Self::default();
+
+        let Some(path) = keymap_path() else {
+            return (keymap, None);
+        };
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return (keymap, None),
+            Err(e) => return (keymap, Some(format!("{}: {}", path.display(), e))),
+        };
+
+        let mut configured = keymap.clone();
+        match configured.apply_config(&contents) {
+            Ok(_) => (configured, None),
+            Err(e) => (keymap, Some(format!("{}: {}", path.display(), e))),
+        }
+    }
+
+    /// Help text listing the keys of `actions`, like `q/esc - quit, s - solve`.
+    pub fn help(&self, mode: Mode, actions: &[(Action, &str)]) -> String {
+        actions
+            .iter()
+            .filter_map(|&(action, label)| {
+                let keys = self.keys(mode, action);
+                (!keys.is_empty()).then(|| {
+                    let keys: Vec<String> = keys.iter().map(|k| k.to_string()).collect();
+                    format!("{} - {}", keys.join("/"), label)
+                })
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+/// Directory for the config files: `$XDG_CONFIG_HOME/tui-mutagen-solver`,
+/// falling back to `~/.config/tui-mutagen-solver`.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("tui-mutagen-solver"))
+}
+
+/// Path of the keymap file.
+pub fn keymap_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(KEYMAP_FILE_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(s: &str) -> Key {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_keys() {
+        assert_eq!(key("q"), Key::new(KeyCode::Char('q'), KeyModifiers::NONE));
+        assert_eq!(
+            key("ctrl-C"),
+            Key::new(KeyCode::Char('c'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(key("^c"), key("ctrl-c"));
+        assert_eq!(
+            key("shift-tab"),
+            Key::new(KeyCode::BackTab, KeyModifiers::NONE)
+        );
+        assert_eq!(key("backtab"), key("shift-tab"));
+        assert_eq!(key("-"), Key::new(KeyCode::Char('-'), KeyModifiers::NONE));
+        assert_eq!(key("^"), Key::new(KeyCode::Char('^'), KeyModifiers::NONE));
+        assert_eq!(key("f12"), Key::new(KeyCode::F(12), KeyModifiers::NONE));
+        assert_eq!(
+            key("space"),
+            Key::new(KeyCode::Char(' '), KeyModifiers::NONE)
+        );
+        assert!("hyper-x".parse::<Key>().is_err());
+        assert!("nokey".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn shown_keys_read_back() {
+        let keys = [
+            "q",
+            "A",
+            "?",
+            "-",
+            "+",
+            "[",
+            "space",
+            "enter",
+            "esc",
+            "tab",
+            "shift-tab",
+            "backspace",
+            "del",
+            "up",
+            "pgdn",
+            "home",
+            "f1",
+            "ctrl-c",
+            "ctrl-w",
+            "alt-x",
+            "ctrl-alt-left",
+            "shift-up",
+        ];
+        for s in keys {
+            let shown = key(s).to_string();
+            assert_eq!(
+                shown.parse::<Key>(),
+                Ok(key(s)),
+                "{} is shown as {}",
+                s,
+                shown
+            );
+        }
+
+        for (_, key, _) in Keymap::default().bindings {
+            assert_eq!(key.to_string().parse::<Key>(), Ok(key));
+        }
+    }
+
+    #[test]
+    fn config_replaces_the_keys_of_an_action() {
+        let mut keymap = Keymap::default();
+        keymap
+            .apply_config(
+                "# my keys\n\n[normal]\nquit = ctrl-q\nsolve = s, f5\n\n[sandbox]\nup = ctrl-p\n",
+            )
+            .unwrap();
+
+        assert_eq!(keymap.keys(Mode::Normal, Action::Quit), vec![key("ctrl-q")]);
+        assert_eq!(
+            keymap.keys(Mode::Normal, Action::Solve),
+            vec![key("s"), key("f5")]
+        );
+        assert_eq!(keymap.keys(Mode::Sandbox, Action::Up), vec![key("ctrl-p")]);
+        // Other modes keep their defaults.
+        assert_eq!(
+            keymap.keys(Mode::Browser, Action::Up),
+            vec![key("up"), key("k")]
+        );
+    }
+
+    #[test]
+    fn config_moves_a_key_to_its_new_action() {
+        let mut keymap = Keymap::default();
+        keymap.apply_config("[normal]\nsave = s\n").unwrap();
+
+        let press = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::NONE);
+        assert_eq!(keymap.action(Mode::Normal, press), Some(Action::Save));
+        assert!(keymap.keys(Mode::Normal, Action::Solve).is_empty());
+    }
+
+    #[test]
+    fn config_sections_cover_every_mode() {
+        for mode in Mode::ALL {
+            let mut keymap = Keymap::default();
+            let config = format!("[{}]\nhelp = f2\n", mode.name());
+            assert_eq!(keymap.apply_config(&config), Ok(()));
+            assert_eq!(keymap.keys(mode, Action::Help), vec![key("f2")]);
+        }
+    }
+
+    #[test]
+    fn config_errors_name_the_line() {
+        let mut keymap = Keymap::default();
+        assert_eq!(
+            keymap.apply_config("[normal]\n\n[nowhere]\n"),
+            Err("line 3: unknown mode 'nowhere'".to_string())
+        );
+        assert_eq!(
+            keymap.apply_config("fly = f\n"),
+            Err("line 1: unknown action 'fly'".to_string())
+        );
+        assert_eq!(
+            keymap.apply_config("# keys\nquit q\n"),
+            Err("line 2: expected 'action = keys'".to_string())
+        );
+        assert_eq!(
+            keymap.apply_config("quit = hyper-q\n"),
+            Err("line 1: unknown modifier 'hyper' in 'hyper-q'".to_string())
+        );
+    }
+}
@@ -21,3 +21,42 @@ pub mod combinator;
 
 // Solver module
 pub mod solver;
+
+// Command line interface module
+pub mod cli;
+
+// Batch runner module
+pub mod batch;
+
+// Puzzle generator module
+pub mod generator;
+
+// Reagent editor module
+pub mod editor;
+
+// Sandbox module
+pub mod sandbox;
+
+// Keymap module
+pub mod keymap;
+
+// Report export module
+pub mod export;
+
+// Command palette module
+pub mod palette;
+
+// File browser and path completion module
+pub mod files;
+
+// Settings and session module
+pub mod settings;
+
+// Colour theme module
+pub mod theme;
+
+// Clipboard module
+pub mod clipboard;
+
+// Search tree explorer module
+pub mod tree;
@@ -1,20 +1,62 @@
+use clap::Parser;
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
 use tui_mutagen_solver::app::{App, AppResult};
+use tui_mutagen_solver::cli::{
+    run_batch_command, run_generate, run_solve, Cli, Command, EXIT_ERROR,
+};
 use tui_mutagen_solver::event::{Event, EventHandler};
-use tui_mutagen_solver::handler::handle_key_events;
+use tui_mutagen_solver::handler::{handle_key_events, handle_mouse_events, handle_paste_event};
+use tui_mutagen_solver::reagent::{stdin_is_piped, STDIN_FILE_NAME};
 use tui_mutagen_solver::tui::Tui;
 
-fn main() -> AppResult<()> {
-    // Create an application.
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(Command::Solve(args)) => run_solve(&args),
+        Some(Command::Batch(args)) => run_batch_command(&args),
+        Some(Command::Generate(args)) => run_generate(&args),
+        Some(Command::Tui { file }) => exit_code(run_tui(file.or_else(piped_file), cli.config)),
+        None => exit_code(run_tui(piped_file(), cli.config)),
+    }
+}
+
+/// Loads piped input when the TUI is started without a file.
+fn piped_file() -> Option<String> {
+    stdin_is_piped().then(|| STDIN_FILE_NAME.to_string())
+}
+
+fn exit_code(result: AppResult<()>) -> ExitCode {
+    match result {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+fn run_tui(file: Option<String>, config: Option<PathBuf>) -> AppResult<()> {
+    // Create an application and restore the last session.
     let mut app = App::new();
+    app.load_keymap();
+    app.load_settings(config);
+    match file {
+        Some(file) => app.open_file(&file),
+        None => app.restore_session(),
+    }
     // Initialize the terminal user interface.
     let backend = CrosstermBackend::new(io::stderr());
     let terminal = Terminal::new(backend)?;
     let events = EventHandler::new(250);
     let mut tui = Tui::new(terminal, events);
     tui.init()?;
+    let (width, height) = crossterm::terminal::size()?;
+    app.resize(width, height);
 
     // Start the main loop.
     while app.running {
@@ -24,12 +66,17 @@ fn main() -> AppResult<()> {
         match tui.events.next()? {
             Event::Tick => app.tick(),
             Event::Key(key_event) => handle_key_events(key_event, &mut app)?,
-            Event::Mouse(_) => {}
-            Event::Resize(_, _) => {}
+            Event::Mouse(mouse_event) => handle_mouse_events(mouse_event, &mut app)?,
+            Event::Resize(width, height) => app.resize(width, height),
+            Event::Paste(text) => handle_paste_event(&text, &mut app)?,
         }
     }
 
     // Exit the user interface.
     tui.exit()?;
+
+    if let Err(e) = app.save_settings() {
+        eprintln!("error: cannot save settings: {}", e);
+    }
     Ok(())
 }
@@ -0,0 +1,316 @@
+// Module: palette
+use crate::app::{Setting, WatchMode};
+use crate::clipboard::CopyFormat;
+use crate::export::ExportFormat;
+use crate::files::complete_path;
+use crate::keymap::Action;
+use crate::solver::Strategy;
+use crate::theme::Theme;
+
+/// Actions that can be run from the palette by their keymap name.
+pub const PALETTE_ACTIONS: [Action; 19] = [
+    Action::Solve,
+    Action::Load,
+    Action::Browse,
+    Action::Recent,
+    Action::NewPuzzle,
+    Action::ClosePuzzle,
+    Action::NextPuzzle,
+    Action::PreviousPuzzle,
+    Action::Save,
+    Action::SaveAs,
+    Action::ToggleSandbox,
+    Action::SearchTree,
+    Action::CycleTheme,
+    Action::NextTab,
+    Action::PreviousTab,
+    Action::FocusNext,
+    Action::FocusPrevious,
+    Action::Help,
+    Action::Quit,
+];
+
+/// Commands that take arguments, with their usage.
+pub const COMMANDS: [(&str, &str); 7] = [
+    ("open", "open <file>"),
+    ("strategy", "strategy <priority|beam>"),
+    ("set", "set <setting> <value>"),
+    ("export", "export <json|md|html|dot> [file]"),
+    ("watch", "watch <off|reload|solve>"),
+    ("theme", "theme <dark|light|high-contrast|deuteranopia>"),
+    ("copy", "copy [comma|lines]"),
+];
+
+/// A parsed palette command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Does the same as the key bound to the action.
+    Run(Action),
+    Open(String),
+    Set(Setting, String),
+    Export(ExportFormat, Option<String>),
+    Watch(WatchMode),
+    Theme(Theme),
+    Copy(Option<CopyFormat>),
+}
+
+/// Parses a command line like `set max-depth 20`.
+pub fn parse_command(input: &str) -> Result<Command, String> {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let usage = |name: &str| {
+        let usage = COMMANDS
+            .iter()
+            .find(|(command, _)| *command == name)
+            .map_or(name, |(_, usage)| usage);
+        format!("usage: {}", usage)
+    };
+
+    match words.as_slice() {
+        [] => Err("no command".to_string()),
+        // Everything after `open` is the path, spaces included.
+        ["open", _, ..] => {
+            let file = input.trim().strip_prefix("open").unwrap_or_default();
+            Ok(Command::Open(file.trim().to_string()))
+        }
+        ["open"] => Err(usage("open")),
+        ["strategy", strategy] => Ok(Command::Set(Setting::Strategy, strategy.to_string())),
+        ["strategy", ..] => Err(usage("strategy")),
+        ["set", setting, value] => match Setting::from_name(setting) {
+            Some(setting) => Ok(Command::Set(setting, value.to_string())),
+            None => Err(format!("unknown setting '{}'", setting)),
+        },
+        ["set", ..] => Err(usage("set")),
+        ["export", format] => Ok(Command::Export(format.parse()?, None)),
+        ["export", format, file] => Ok(Command::Export(format.parse()?, Some(file.to_string()))),
+        ["export", ..] => Err(usage("export")),
+        ["watch", watch] => Ok(Command::Watch(watch.parse()?)),
+        ["watch", ..] => Err(usage("watch")),
+        ["theme", theme] => Ok(Command::Theme(theme.parse()?)),
+        ["theme", ..] => Err(usage("theme")),
+        ["copy"] => Ok(Command::Copy(None)),
+        ["copy", format] => Ok(Command::Copy(Some(format.parse()?))),
+        ["copy", ..] => Err(usage("copy")),
+        [name] => PALETTE_ACTIONS
+            .into_iter()
+            .find(|action| action.name() == *name)
+            .map(Command::Run)
+            .ok_or_else(|| format!("unknown command '{}'", name)),
+        [name, ..] => Err(format!("'{}' takes no arguments", name)),
+    }
+}
+
+/// Words that can complete the last word of `input`.
+fn candidates(input: &str) -> Vec<String> {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    // The word being typed, empty right after a space.
+    let position = match input.ends_with(char::is_whitespace) || input.is_empty() {
+        true => words.len(),
+        false => words.len() - 1,
+    };
+
+    let strategies = || {
+        [Strategy::Priority, Strategy::Beam]
+            .map(|s| s.to_string())
+            .to_vec()
+    };
+
+    match (position, words.first().copied()) {
+        (0, _) => PALETTE_ACTIONS
+            .iter()
+            .map(|action| action.name())
+            .chain(COMMANDS.iter().map(|(command, _)| *command))
+            .map(|name| name.to_string())
+            .collect(),
+        (1, Some("strategy")) => strategies(),
+        (1, Some("set")) => Setting::ALL.iter().map(|s| s.name().to_string()).collect(),
+        (1, Some("export")) => ExportFormat::ALL.iter().map(|f| f.to_string()).collect(),
+        (1, Some("watch")) => WatchMode::ALL.iter().map(|w| w.to_string()).collect(),
+        (1, Some("theme")) => Theme::ALL.iter().map(|t| t.to_string()).collect(),
+        (1, Some("copy")) => CopyFormat::ALL.iter().map(|f| f.to_string()).collect(),
+        (2, Some("set")) if words.get(1) == Some(&Setting::Strategy.name()) => strategies(),
+        (2, Some("set")) if words.get(1) == Some(&Setting::RecordTree.name()) => {
+            vec!["on".to_string(), "off".to_string()]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Command line typed after `:`.
+#[derive(Clone, Debug, Default)]
+pub struct Palette {
+    pub input: String,
+    /// Completions offered by the last completion that was not unique.
+    pub candidates: Vec<String>,
+}
+
+impl Palette {
+    pub fn enter_char(&mut self, new_char: char) {
+        self.input.push(new_char);
+        self.candidates.clear();
+    }
+
+    pub fn delete_char(&mut self) {
+        self.input.pop();
+        self.candidates.clear();
+    }
+
+    /// Completes the word being typed as far as it is unambiguous, listing
+    /// the candidates when there is more than one. Everything after `open`
+    /// is completed as a path, which may hold spaces.
+    pub fn complete(&mut self) {
+        let path = self
+            .input
+            .trim_start()
+            .strip_prefix("open")
+            .filter(|rest| rest.starts_with(char::is_whitespace));
+        if let Some(path) = path {
+            let path_start = self.input.len() - path.trim_start().len();
+            let (completed, candidates) = complete_path(&self.input[path_start..]);
+            self.input.truncate(path_start);
+            self.input.push_str(&completed);
+            self.candidates = candidates;
+            return;
+        }
+
+        let word_start = self
+            .input
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let word = &self.input[word_start..];
+
+        let matches: Vec<String> = candidates(&self.input)
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .collect();
+
+        let completion = match matches.as_slice() {
+            [] => return,
+            [only] => format!("{} ", only),
+            [first, rest @ ..] => rest.iter().fold(first.clone(), |prefix, candidate| {
+                prefix
+                    .chars()
+                    .zip(candidate.chars())
+                    .take_while(|(a, b)| a == b)
+                    .map(|(a, _)| a)
+                    .collect()
+            }),
+        };
+
+        self.input.truncate(word_start);
+        self.input.push_str(&completion);
+        self.candidates = match matches.len() {
+            1 => Vec::new(),
+            _ => matches,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn completed(input: &str) -> Palette {
+        let mut palette = Palette {
+            input: input.to_string(),
+            candidates: Vec::new(),
+        };
+        palette.complete();
+        palette
+    }
+
+    #[test]
+    fn parses_actions_and_commands() {
+        assert_eq!(parse_command("solve"), Ok(Command::Run(Action::Solve)));
+        assert_eq!(
+            parse_command("  save-as "),
+            Ok(Command::Run(Action::SaveAs))
+        );
+        assert_eq!(
+            parse_command("set max-depth 20"),
+            Ok(Command::Set(Setting::MaxDepth, "20".to_string()))
+        );
+        assert_eq!(
+            parse_command("strategy beam"),
+            Ok(Command::Set(Setting::Strategy, "beam".to_string()))
+        );
+        assert_eq!(
+            parse_command("export md out.md"),
+            Ok(Command::Export(
+                ExportFormat::Markdown,
+                Some("out.md".to_string())
+            ))
+        );
+        assert_eq!(
+            parse_command("export json"),
+            Ok(Command::Export(ExportFormat::Json, None))
+        );
+        assert_eq!(parse_command("copy"), Ok(Command::Copy(None)));
+    }
+
+    #[test]
+    fn reports_mistakes() {
+        assert_eq!(parse_command(""), Err("no command".to_string()));
+        assert_eq!(parse_command("open"), Err("usage: open <file>".to_string()));
+        assert_eq!(
+            parse_command("set bogus 1"),
+            Err("unknown setting 'bogus'".to_string())
+        );
+        assert_eq!(
+            parse_command("fly"),
+            Err("unknown command 'fly'".to_string())
+        );
+        assert_eq!(
+            parse_command("solve now"),
+            Err("'solve' takes no arguments".to_string())
+        );
+        assert!(parse_command("export pdf").is_err());
+    }
+
+    #[test]
+    fn open_takes_paths_with_spaces() {
+        assert_eq!(
+            parse_command("open examples/wiki_eg.txt"),
+            Ok(Command::Open("examples/wiki_eg.txt".to_string()))
+        );
+        assert_eq!(
+            parse_command("open my  puzzles/first one.txt "),
+            Ok(Command::Open("my  puzzles/first one.txt".to_string()))
+        );
+    }
+
+    #[test]
+    fn completes_commands_and_arguments() {
+        assert_eq!(completed("sol").input, "solve ");
+        assert_eq!(completed("set max").input, "set max-");
+        assert_eq!(completed("set max-d").input, "set max-depth ");
+        assert_eq!(completed("set strategy b").input, "set strategy beam ");
+        assert_eq!(completed("theme deu").input, "theme deuteranopia ");
+        assert_eq!(completed("set\u{a0}ma").input, "set\u{a0}max-");
+
+        let palette = completed("se");
+        assert_eq!(palette.input, "se");
+        assert!(palette.candidates.contains(&"search-tree".to_string()));
+        assert!(palette.candidates.contains(&"set".to_string()));
+
+        assert_eq!(completed("fly").input, "fly");
+    }
+
+    #[test]
+    fn completes_open_as_a_path() {
+        let dir = std::env::temp_dir().join(format!("palette test {}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("first puzzle.txt"), "").unwrap();
+
+        let typed = format!("open {}/fir", dir.display());
+        let palette = completed(&typed);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            palette.input,
+            format!("open {}/first puzzle.txt", dir.display())
+        );
+    }
+}
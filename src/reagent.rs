@@ -1,8 +1,17 @@
 use std::hash::{Hash, Hasher};
 // Module: reagent
-use std::io::Read;
+use std::io::{IsTerminal, Read};
 use std::{fs, io};
 
+/// Lines starting with this are comments.
+pub const COMMENT_PREFIX: &str = "#";
+
+/// Name of the reagent describing the target sequence.
+pub const EXITUS_NAME: &str = "Exitus-1";
+
+/// File name standing for standard input.
+pub const STDIN_FILE_NAME: &str = "-";
+
 #[derive(PartialEq, Clone, Eq)]
 pub struct Reagent {
     pub score: Option<usize>,
@@ -17,6 +26,16 @@ impl Hash for Reagent {
     }
 }
 
+impl Reagent {
+    /// The reagent as a line of a puzzle file, without the line break.
+    pub fn to_line(&self) -> String {
+        std::iter::once(self.name.as_str())
+            .chain(self.atoms.iter().map(|a| a.as_str()))
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+}
+
 impl std::fmt::Display for Reagent {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "\t{} ", self.name)?;
@@ -31,16 +50,103 @@ impl std::fmt::Debug for Reagent {
     }
 }
 
+/// Reads a puzzle file, or standard input when `file_path` is [`STDIN_FILE_NAME`].
 pub fn load_reagents(file_path: &str) -> io::Result<String> {
-    let mut file = fs::File::open(file_path)?;
     let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
+
+    if file_path == STDIN_FILE_NAME {
+        let mut stdin = io::stdin();
+        if stdin.is_terminal() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "nothing piped to standard input",
+            ));
+        }
+        stdin.read_to_string(&mut contents)?;
+    } else {
+        let mut file = fs::File::open(file_path)?;
+        file.read_to_string(&mut contents)?;
+    }
+
     Ok(contents)
 }
 
-// pub fn validate_reagents(reagents: &Vec<Reagent>) -> bool {
-//     false
-// }
+/// Whether a puzzle is being piped into the program.
+pub fn stdin_is_piped() -> bool {
+    !io::stdin().is_terminal()
+}
+
+/// Whether `line` is a comment rather than a reagent.
+pub fn is_comment(line: &str) -> bool {
+    line.trim_start().starts_with(COMMENT_PREFIX)
+}
+
+/// Problem found on a line of a reagent list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReagentIssue {
+    /// Zero-based line, `None` for problems with the list as a whole.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl std::fmt::Display for ReagentIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line + 1, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Checks a reagent list for mistakes [`parse_reagents`] would silently accept.
+pub fn validate_reagents(contents: &str) -> Vec<ReagentIssue> {
+    let mut issues = Vec::new();
+    let mut names: Vec<&str> = Vec::new();
+    let mut issue = |line: usize, message: String| {
+        issues.push(ReagentIssue {
+            line: Some(line),
+            message,
+        })
+    };
+
+    for (i, line) in contents.lines().enumerate() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let Some((&name, atoms)) = parts.split_first() else {
+            continue;
+        };
+        if is_comment(line) {
+            continue;
+        }
+
+        if names.contains(&name) {
+            issue(i, format!("{} is defined twice", name));
+        }
+        names.push(name);
+
+        if atoms.is_empty() {
+            issue(i, format!("{} has no atoms", name));
+        }
+
+        for (j, atom) in atoms.iter().enumerate() {
+            if atom.trim_start_matches('-').is_empty() || atom.starts_with("--") {
+                issue(i, format!("{} has a malformed atom '{}'", name, atom));
+            } else if atoms[..j].contains(atom) {
+                issue(i, format!("{} repeats atom {}", name, atom));
+            } else if name == EXITUS_NAME && atom.starts_with('-') {
+                issue(i, format!("{} cannot remove atoms", name));
+            }
+        }
+    }
+
+    if !names.contains(&EXITUS_NAME) {
+        issues.push(ReagentIssue {
+            line: None,
+            message: format!("No {} found", EXITUS_NAME),
+        });
+    }
+
+    issues
+}
 
 pub fn parse_reagents(contents: &str) -> (Option<Reagent>, Vec<Reagent>) {
     let mut reagents = Vec::new();
@@ -48,6 +154,9 @@ pub fn parse_reagents(contents: &str) -> (Option<Reagent>, Vec<Reagent>) {
 
     for line in contents.lines() {
         let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.is_empty() || is_comment(line) {
+            continue;
+        }
         let reagent_name: String = parts[0].to_string();
         let atoms: Vec<String> = parts[1..].iter().map(|s| s.to_string()).collect();
 
@@ -57,7 +166,7 @@ pub fn parse_reagents(contents: &str) -> (Option<Reagent>, Vec<Reagent>) {
             score: None,
         };
 
-        if reagent_name == "Exitus-1" {
+        if reagent_name == EXITUS_NAME {
             exitus = Some(reagent);
         } else {
             reagents.push(reagent);
@@ -66,3 +175,168 @@ pub fn parse_reagents(contents: &str) -> (Option<Reagent>, Vec<Reagent>) {
 
     (exitus, reagents)
 }
+
+/// Reads a puzzle file and splits it into its exitus and reagents.
+pub fn read_puzzle(file_path: &str) -> Result<(Reagent, Vec<Reagent>), String> {
+    let contents =
+        load_reagents(file_path).map_err(|e| format!("Error loading reagents: {}", e))?;
+
+    split_puzzle(file_path, &contents)
+}
+
+/// Parses puzzle text read from `file_path`, failing when it has no exitus.
+pub fn split_puzzle(file_path: &str, contents: &str) -> Result<(Reagent, Vec<Reagent>), String> {
+    match parse_reagents(contents) {
+        (Some(exitus), reagents) => Ok((exitus, reagents)),
+        (None, _) => Err(format!("No exitus found in {}", file_path)),
+    }
+}
+
+/// Writes a puzzle back in the reagent file format.
+///
+/// Lines of `template`, usually the text the puzzle was read from, are kept
+/// as they are so comments, blank lines and ordering survive. Reagent lines
+/// are only rewritten when the reagent changed and dropped when it is gone;
+/// reagents the template does not mention are appended.
+pub fn format_reagents(template: &str, exitus: &Reagent, reagents: &[Reagent]) -> String {
+    let puzzle: Vec<&Reagent> = std::iter::once(exitus).chain(reagents.iter()).collect();
+    let mut written = vec![false; puzzle.len()];
+    let mut contents = String::new();
+
+    for line in template.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let Some((&name, atoms)) = parts.split_first() else {
+            contents.push_str(line);
+            contents.push('\n');
+            continue;
+        };
+        if is_comment(line) {
+            contents.push_str(line);
+            contents.push('\n');
+            continue;
+        }
+
+        let found = puzzle
+            .iter()
+            .enumerate()
+            .position(|(i, r)| !written[i] && r.name == name);
+
+        if let Some(i) = found {
+            written[i] = true;
+            match puzzle[i].atoms == atoms {
+                true => contents.push_str(line),
+                false => contents.push_str(&puzzle[i].to_line()),
+            }
+            contents.push('\n');
+        }
+    }
+
+    for (i, reagent) in puzzle.iter().enumerate() {
+        if !written[i] {
+            contents.push_str(&reagent.to_line());
+            contents.push('\n');
+        }
+    }
+
+    contents
+}
+
+/// Writes a puzzle to `file_path`, laid out like `template`.
+pub fn save_reagents(
+    file_path: &str,
+    template: &str,
+    exitus: &Reagent,
+    reagents: &[Reagent],
+) -> io::Result<()> {
+    fs::write(file_path, format_reagents(template, exitus, reagents))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str = "# Mutagen puzzle, from the wiki
+Exitus-1   AA BB CC
+
+# Echo
+Echo-1 AA -DD
+  Echo-2 BB\tCC
+# Helicon is next
+Helicon-1 DD
+";
+
+    fn reagent(name: &str, atoms: &[&str]) -> Reagent {
+        Reagent {
+            name: name.to_string(),
+            atoms: atoms.iter().map(|a| a.to_string()).collect(),
+            score: None,
+        }
+    }
+
+    #[test]
+    fn unchanged_puzzle_formats_back_to_the_original() {
+        let (exitus, reagents) = parse_reagents(PUZZLE);
+        let exitus = exitus.unwrap();
+
+        assert_eq!(exitus, reagent(EXITUS_NAME, &["AA", "BB", "CC"]));
+        assert_eq!(reagents.len(), 3);
+        assert_eq!(format_reagents(PUZZLE, &exitus, &reagents), PUZZLE);
+    }
+
+    #[test]
+    fn example_puzzles_format_back_to_the_original() {
+        for entry in fs::read_dir("examples").unwrap() {
+            let path = entry.unwrap().path();
+            let contents = fs::read_to_string(&path).unwrap();
+            let (Some(exitus), reagents) = parse_reagents(&contents) else {
+                continue;
+            };
+
+            let formatted = format_reagents(&contents, &exitus, &reagents);
+            assert_eq!(
+                formatted.trim_end(),
+                contents.trim_end(),
+                "{}",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn edits_keep_comments_and_order() {
+        let (exitus, mut reagents) = parse_reagents(PUZZLE);
+        reagents[1] = reagent("Echo-2", &["BB", "-AA"]);
+        reagents.remove(2);
+        reagents.push(reagent("Io-1", &["CC"]));
+
+        let formatted = format_reagents(PUZZLE, &exitus.unwrap(), &reagents);
+        assert_eq!(
+            formatted,
+            "# Mutagen puzzle, from the wiki
+Exitus-1   AA BB CC
+
+# Echo
+Echo-1 AA -DD
+Echo-2 BB -AA
+# Helicon is next
+Io-1 CC
+"
+        );
+    }
+
+    #[test]
+    fn saved_file_reads_back_as_the_same_puzzle() {
+        let (exitus, mut reagents) = parse_reagents(PUZZLE);
+        let exitus = exitus.unwrap();
+        reagents.push(reagent("Solis-1", &["AA", "-CC"]));
+
+        let path = std::env::temp_dir().join(format!("reagent_test_{}.txt", std::process::id()));
+        let path = path.to_string_lossy();
+        save_reagents(&path, PUZZLE, &exitus, &reagents).unwrap();
+        let saved = load_reagents(&path).unwrap();
+        fs::remove_file(path.as_ref()).unwrap();
+
+        assert!(saved.starts_with("# Mutagen puzzle, from the wiki\n"));
+        assert_eq!(parse_reagents(&saved), (Some(exitus), reagents));
+    }
+}
@@ -0,0 +1,97 @@
+use crate::combinator::Combinator;
+use crate::reagent::Reagent;
+
+/// How an atom of the sandbox sequence lines up with the exitus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AtomMatch {
+    /// Same atom at the same position.
+    Match,
+    /// Different atoms at this position.
+    Mismatch,
+    /// The sequence is longer than the exitus.
+    Extra,
+    /// The exitus is longer than the sequence.
+    Missing,
+}
+
+/// One column of a sequence aligned against the exitus.
+#[derive(Clone, Debug)]
+pub struct AlignedAtom {
+    pub sequence: Option<String>,
+    pub exitus: Option<String>,
+    pub status: AtomMatch,
+}
+
+/// Playground where reagents are applied by hand.
+#[derive(Clone, Debug)]
+pub struct Sandbox {
+    /// Combinator after every applied reagent, the last one is current.
+    pub steps: Vec<Combinator>,
+    /// Outcome of the last request to finish the path with the solver.
+    pub finished: Option<Option<Vec<String>>>,
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sandbox {
+    /// Constructs a new instance of [`Sandbox`] with an empty sequence.
+    pub fn new() -> Self {
+        Self {
+            steps: Vec::new(),
+            finished: None,
+        }
+    }
+
+    /// The combinator after the last applied reagent.
+    pub fn current(&self) -> Combinator {
+        self.steps.last().cloned().unwrap_or(Combinator {
+            sequence: Vec::new(),
+            reagent_path: Vec::new(),
+        })
+    }
+
+    pub fn apply(&mut self, reagent: &Reagent) {
+        let mut combinator = self.current();
+        combinator.add_reagent(reagent);
+        self.steps.push(combinator);
+        self.finished = None;
+    }
+
+    /// Takes back the last applied reagent.
+    pub fn undo(&mut self) {
+        self.steps.pop();
+        self.finished = None;
+    }
+
+    /// Lines the current sequence up against the exitus, position by position.
+    pub fn alignment(&self, exitus: &Reagent) -> Vec<AlignedAtom> {
+        align(&self.current().sequence, &exitus.atoms)
+    }
+}
+
+/// Lines `sequence` up against the exitus, position by position.
+pub fn align(sequence: &[String], exitus: &[String]) -> Vec<AlignedAtom> {
+    let len = sequence.len().max(exitus.len());
+
+    (0..len)
+        .map(|i| {
+            let sequence = sequence.get(i).cloned();
+            let exitus = exitus.get(i).cloned();
+            let status = match (&sequence, &exitus) {
+                (Some(s), Some(e)) if s == e => AtomMatch::Match,
+                (Some(_), Some(_)) => AtomMatch::Mismatch,
+                (Some(_), None) => AtomMatch::Extra,
+                (None, _) => AtomMatch::Missing,
+            };
+            AlignedAtom {
+                sequence,
+                exitus,
+                status,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,166 @@
+// Module: settings
+use crate::app::{Setting, WatchMode};
+use crate::clipboard::{ClipboardMethod, CopyFormat};
+use crate::keymap::config_dir;
+use crate::solver::SolverOptions;
+use crate::theme::Theme;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the settings file inside the config directory.
+pub const SETTINGS_FILE_NAME: &str = "settings.conf";
+
+/// Number of recently opened files remembered.
+pub const MAX_RECENT_FILES: usize = 10;
+
+/// Prefix of the sections holding key bindings, e.g. `[keys.normal]`.
+const KEYS_SECTION_PREFIX: &str = "keys.";
+
+/// Session and preferences kept between runs.
+///
+/// The file uses the keymap file syntax: a `[session]` section with the
+/// recent files (most recent first), the puzzles open at exit and the watch
+/// mode, `[solver]` with the
+/// strategy and limits, `[display]` with the theme, `[clipboard]` with how
+/// solutions are copied, and `[keys.<mode>]` sections with key bindings.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Settings {
+    pub recent_files: Vec<String>,
+    /// Files of the puzzle tabs, in tab order.
+    pub open_files: Vec<String>,
+    pub watch: WatchMode,
+    pub solver_options: SolverOptions,
+    pub theme: Theme,
+    pub copy_format: CopyFormat,
+    pub clipboard_method: ClipboardMethod,
+    /// Key bindings in the keymap file syntax, with plain `[mode]` headers.
+    pub keys: String,
+}
+
+impl Settings {
+    /// Parses a settings file. Errors name the line they were found on.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut settings = Settings::default();
+        let mut section = String::new();
+
+        for (i, line) in contents.lines().enumerate() {
+            let trimmed = line.trim();
+            let error = |e: String| format!("line {}: {}", i + 1, e);
+
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = trimmed.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+                if let Some(mode) = section.strip_prefix(KEYS_SECTION_PREFIX) {
+                    settings.keys.push_str(&format!("[{}]\n", mode));
+                }
+                continue;
+            }
+
+            if section.starts_with(KEYS_SECTION_PREFIX) {
+                settings.keys.push_str(trimmed);
+                settings.keys.push('\n');
+                continue;
+            }
+
+            let (key, value) = trimmed
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .ok_or_else(|| error("expected 'name = value'".to_string()))?;
+
+            match (section.as_str(), key) {
+                ("session", "recent") => settings.recent_files.push(value.to_string()),
+                ("session", "open") => settings.open_files.push(value.to_string()),
+                ("session", "watch") => settings.watch = value.parse().map_err(error)?,
+                ("solver", name) => Setting::from_name(name)
+                    .ok_or_else(|| error(format!("unknown solver setting '{}'", name)))?
+                    .set(&mut settings.solver_options, value)
+                    .map_err(error)?,
+                ("display", "theme") => settings.theme = value.parse().map_err(error)?,
+                ("clipboard", "format") => settings.copy_format = value.parse().map_err(error)?,
+                ("clipboard", "method") => {
+                    settings.clipboard_method = value.parse().map_err(error)?
+                }
+                (section, key) => {
+                    return Err(error(format!("unknown setting '{}' in [{}]", key, section)))
+                }
+            }
+        }
+
+        settings.recent_files.truncate(MAX_RECENT_FILES);
+        Ok(settings)
+    }
+
+    /// The settings in the file format read by [`Settings::parse`].
+    pub fn to_file_string(&self) -> String {
+        let mut contents = String::from(
+            "# Rewritten on exit: edited values are kept, comments are not.\n\n[session]\n",
+        );
+
+        for file in &self.recent_files {
+            contents.push_str(&format!("recent = {}\n", file));
+        }
+        for file in &self.open_files {
+            contents.push_str(&format!("open = {}\n", file));
+        }
+        contents.push_str(&format!("watch = {}\n\n[solver]\n", self.watch));
+
+        for setting in Setting::ALL {
+            contents.push_str(&format!(
+                "{} = {}\n",
+                setting.name(),
+                setting.value(&self.solver_options)
+            ));
+        }
+
+        contents.push_str(&format!("\n[display]\ntheme = {}\n", self.theme));
+        contents.push_str(&format!(
+            "\n[clipboard]\nformat = {}\nmethod = {}\n",
+            self.copy_format, self.clipboard_method
+        ));
+
+        for line in self.keys.lines() {
+            match line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                Some(mode) => contents.push_str(&format!("\n[{}{}]\n", KEYS_SECTION_PREFIX, mode)),
+                None => {
+                    contents.push_str(line);
+                    contents.push('\n');
+                }
+            }
+        }
+
+        contents
+    }
+
+    /// Reads the settings file; a missing file gives the defaults.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Settings::parse(&contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Settings::default()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Writes the settings file, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_file_string())
+    }
+
+    /// Moves `file` to the front of the recent files.
+    pub fn add_recent_file(&mut self, file: &str) {
+        self.recent_files.retain(|f| f != file);
+        self.recent_files.insert(0, file.to_string());
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+}
+
+/// Default path of the settings file.
+pub fn settings_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(SETTINGS_FILE_NAME))
+}
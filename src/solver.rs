@@ -3,8 +3,331 @@ use crate::reagent::Reagent;
 use ordered_float::OrderedFloat;
 use priority_queue::PriorityQueue;
 use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{mpsc::channel, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
 
-const MAX_DEPTH: usize = 15;
+/// Default maximum length of a reagent path.
+pub const MAX_DEPTH: usize = 15;
+
+/// Default number of nodes expanded before a search gives up.
+pub const MAX_ITERATIONS: usize = 2500;
+
+/// Default number of candidates kept per depth by the beam search.
+pub const BEAM_WIDTH: usize = 50;
+
+/// Search strategy used to extend a start reagent into a full path.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// Best-first search ordered by the heuristic score.
+    #[default]
+    Priority,
+    /// Level-by-level search keeping only the best candidates of each depth.
+    Beam,
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Strategy::Priority => write!(f, "priority"),
+            Strategy::Beam => write!(f, "beam"),
+        }
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "priority" => Ok(Strategy::Priority),
+            "beam" => Ok(Strategy::Beam),
+            _ => Err(format!(
+                "unknown strategy '{}' (expected priority or beam)",
+                s
+            )),
+        }
+    }
+}
+
+/// Limits and strategy shared by every search of a solver run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SolverOptions {
+    pub strategy: Strategy,
+    pub max_depth: usize,
+    pub max_iterations: usize,
+    pub beam_width: usize,
+    /// Keeps the nodes the priority search expands, see [`SearchTree`].
+    pub record_tree: bool,
+}
+
+impl Default for SolverOptions {
+    fn default() -> Self {
+        Self {
+            strategy: Strategy::default(),
+            max_depth: MAX_DEPTH,
+            max_iterations: MAX_ITERATIONS,
+            beam_width: BEAM_WIDTH,
+            record_tree: false,
+        }
+    }
+}
+
+/// Nodes expanded between two progress reports of a search.
+pub const PROGRESS_INTERVAL: usize = 50;
+
+/// How far a search got.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub nodes_expanded: usize,
+    /// Candidates waiting in the priority queue, or kept in the beam.
+    pub queue_size: usize,
+    /// Longest run of exitus atoms any sequence so far started with.
+    pub best_match: usize,
+    /// Length of the path being extended.
+    pub depth: usize,
+    pub elapsed: Duration,
+}
+
+impl SearchStats {
+    /// Share of the search budget used up, between 0 and 1.
+    ///
+    /// The priority search is bounded by its iterations, the beam search by
+    /// its depth.
+    pub fn progress(&self, options: &SolverOptions) -> f64 {
+        let (done, total) = match options.strategy {
+            Strategy::Priority => (self.nodes_expanded, options.max_iterations),
+            Strategy::Beam => (self.depth, options.max_depth),
+        };
+        (done as f64 / total.max(1) as f64).min(1.0)
+    }
+}
+
+/// Progress of the search from one viable start, reported while
+/// [`solve_with_progress`] runs.
+#[derive(Clone, Debug)]
+pub struct StartProgress {
+    /// Index of the start in the viable starts.
+    pub index: usize,
+    pub start: String,
+    pub stats: SearchStats,
+    pub finished: bool,
+}
+
+/// Node of a [`SearchTree`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchNode {
+    /// Index of the node this one was reached from, `None` for the start.
+    pub parent: Option<usize>,
+    /// Reagent applied to the parent's sequence.
+    pub reagent: String,
+    pub sequence: Vec<String>,
+    /// Heuristic score the node was queued with.
+    pub score: f32,
+    /// Number of reagents in the path leading here.
+    pub depth: usize,
+}
+
+/// Nodes expanded by a priority search, in the order they were expanded,
+/// plus the node producing the exitus if one was found.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchTree {
+    pub nodes: Vec<SearchNode>,
+    /// Index of the node producing the exitus.
+    pub solution: Option<usize>,
+}
+
+impl SearchTree {
+    /// Indices of the nodes on the way from the start to the solution.
+    pub fn solution_nodes(&self) -> HashSet<usize> {
+        let mut nodes = HashSet::new();
+        let mut current = self.solution;
+
+        while let Some(index) = current {
+            nodes.insert(index);
+            current = self.nodes[index].parent;
+        }
+        nodes
+    }
+
+    fn push(&mut self, node: SearchNode) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+}
+
+/// Outcome of the search started from a single viable start reagent.
+#[derive(Clone, Debug)]
+pub struct StartResult {
+    pub start: Reagent,
+    pub path: Option<Vec<String>>,
+    pub elapsed: Duration,
+    pub stats: SearchStats,
+    /// Explored nodes, when the options asked for them and the strategy
+    /// records them.
+    pub tree: Option<SearchTree>,
+}
+
+/// Everything a solver run produced, in the order the steps were taken.
+#[derive(Clone, Debug)]
+pub struct SolveReport {
+    /// Puzzle the run was started on.
+    pub exitus: Reagent,
+    pub reagents: Vec<Reagent>,
+    /// Limits and strategy the run was started with.
+    pub options: SolverOptions,
+    pub useless_reagents: Vec<Reagent>,
+    pub viable_starts: Vec<Reagent>,
+    pub results: Vec<StartResult>,
+}
+
+impl SolveReport {
+    /// Results that ended in a reagent path.
+    pub fn solutions(&self) -> impl Iterator<Item = &StartResult> {
+        self.results.iter().filter(|r| r.path.is_some())
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.solutions().next().is_some()
+    }
+
+    /// The shortest reagent path found by any start.
+    pub fn best_path(&self) -> Option<&Vec<String>> {
+        self.results
+            .iter()
+            .filter_map(|r| r.path.as_ref())
+            .min_by_key(|path| path.len())
+    }
+
+    /// Step by step replay of a solution, as shown in its solver log tab.
+    pub fn trace_lines(&self, result: &StartResult) -> Vec<String> {
+        let path = result.path.clone().unwrap_or_default();
+        let name_width = path.iter().map(|n| n.len()).max().unwrap_or(0).max(7);
+
+        let mut lines = vec![
+            format!(
+                "Start {}, {} reagents, found in {} microseconds",
+                result.start.name,
+                path.len(),
+                result.elapsed.as_micros()
+            ),
+            format!(
+                "{} nodes expanded, {} left in the queue",
+                result.stats.nodes_expanded, result.stats.queue_size
+            ),
+            String::new(),
+            format!("Path: {}", path.join(" -> ")),
+            String::new(),
+            format!("Step  {:<name_width$}  Sequence", "Reagent"),
+        ];
+
+        for (i, step) in trace_path(&self.reagents, &path)
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+        {
+            lines.push(format!(
+                "{:>4}  {:<name_width$}  {}",
+                i + 1,
+                path[i],
+                step.sequence.join(" ")
+            ));
+        }
+
+        lines.push(format!(
+            "      {:<name_width$}  {}",
+            "Exitus",
+            self.exitus.atoms.join(" ")
+        ));
+        lines
+    }
+
+    /// Human readable log of the run, one entry per step.
+    pub fn log_lines(&self) -> Vec<String> {
+        self.log_entries()
+            .into_iter()
+            .map(|(entry, _)| entry)
+            .collect()
+    }
+
+    /// Index into `results` of the start reported on line `line` of the log,
+    /// counting the line breaks inside entries as the log is shown.
+    pub fn result_at_log_line(&self, line: usize) -> Option<usize> {
+        let mut first = 0;
+        for (entry, result) in self.log_entries() {
+            let lines = entry.split('\n').count();
+            if line < first + lines {
+                // The blank line ending an entry belongs to no start.
+                let text = entry.trim_end_matches('\n').split('\n').count();
+                return result.filter(|_| line < first + text);
+            }
+            first += lines;
+        }
+        None
+    }
+
+    /// Entries of [`SolveReport::log_lines`], each with the index into
+    /// `results` of the start it reports on.
+    fn log_entries(&self) -> Vec<(String, Option<usize>)> {
+        let mut log = vec![("Removing useless reagents...".to_string(), None)];
+
+        let removed = match self.useless_reagents.len() {
+            0 => " \u{21B3}No useless reagents found\n".to_string(),
+            _ => format!(
+                " \u{21B3}Removed {}\n",
+                reagent_names(&self.useless_reagents).join(", ")
+            ),
+        };
+        log.push((removed, None));
+
+        log.push(("Looking for viable start reagents...".to_string(), None));
+
+        match self.viable_starts.len() {
+            0 => {
+                let none = " \u{21B3}No viable start reagents found\n".to_string();
+                log.push((none, None));
+                return log;
+            }
+            _ => log.push((
+                format!(
+                    " \u{21B3}Found {} \n",
+                    reagent_names(&self.viable_starts).join(", ")
+                ),
+                None,
+            )),
+        }
+
+        log.push(("Searching...\n".to_string(), None));
+
+        for (i, result) in self.results.iter().enumerate() {
+            let entry = match &result.path {
+                Some(path) => format!(
+                    "Path for start {} \n \u{21B3}{} \n \u{21B3}found in {} microseconds, {} nodes expanded\n",
+                    result.start.name,
+                    path.join(" -> "),
+                    result.elapsed.as_micros(),
+                    result.stats.nodes_expanded,
+                ),
+                None => format!(
+                    "No path found for start {} ({} nodes expanded, best match {} of {} atoms)",
+                    result.start.name,
+                    result.stats.nodes_expanded,
+                    result.stats.best_match,
+                    self.exitus.atoms.len(),
+                ),
+            };
+            log.push((entry, Some(i)));
+        }
+
+        log
+    }
+}
+
+fn reagent_names(reagents: &[Reagent]) -> Vec<String> {
+    reagents.iter().map(|r| r.name.clone()).collect()
+}
 
 pub fn filter_useless_reagents(
     exitus: &Reagent,
@@ -38,9 +361,30 @@ pub fn filter_useless_reagents(
     (useful_reagents, useless_reagents)
 }
 
+/// Atoms that are neither part of the exitus nor removed by any reagent.
+///
+/// A reagent adding one of these can never be part of a solution.
+pub fn unremovable_atoms(exitus: &Reagent, reagents: &[Reagent]) -> HashSet<String> {
+    let atom_pool: HashSet<&String> = reagents.iter().flat_map(|r| r.atoms.iter()).collect();
+
+    atom_pool
+        .iter()
+        .filter(|atom| {
+            !atom.starts_with('-')
+                && !exitus.atoms.contains(atom)
+                && !atom_pool.contains(&format!("-{}", atom))
+        })
+        .map(|atom| atom.to_string())
+        .collect()
+}
+
 fn contains_ordered_slice(sequence: &[String], slice: &[String]) -> bool {
     let l = slice.len();
 
+    if l > sequence.len() {
+        return false;
+    }
+
     for i in 0..sequence.len() - l + 1 {
         if sequence[i..i + l] == slice[..] {
             return true;
@@ -57,7 +401,8 @@ pub fn get_viable_start_reagents(exitus: &Reagent, reagents: &[Reagent]) -> Vec<
         let mut score: usize = 0;
 
         let mut j = 0;
-        while contains_ordered_slice(&reagent.atoms, &exitus.atoms[0..j]) {
+        while j <= exitus.atoms.len() && contains_ordered_slice(&reagent.atoms, &exitus.atoms[0..j])
+        {
             score = j;
             j += 1;
         }
@@ -70,12 +415,54 @@ pub fn get_viable_start_reagents(exitus: &Reagent, reagents: &[Reagent]) -> Vec<
             });
         }
 
-        viable_starts.sort_by(|a, b| b.score.cmp(&a.score));
+        viable_starts.sort_by_key(|r| std::cmp::Reverse(r.score));
     }
 
     viable_starts
 }
 
+/// Replays `path` from an empty sequence, `None` if it names an unknown reagent.
+pub fn replay_path(reagents: &[Reagent], path: &[String]) -> Option<Combinator> {
+    let mut combinator = Combinator {
+        sequence: Vec::new(),
+        reagent_path: Vec::new(),
+    };
+
+    for name in path {
+        let reagent = reagents.iter().find(|r| &r.name == name)?;
+        combinator.add_reagent(reagent);
+    }
+
+    Some(combinator)
+}
+
+/// Replays `path` step by step, returning the combinator after every reagent.
+///
+/// `None` if the path names an unknown reagent.
+pub fn trace_path(reagents: &[Reagent], path: &[String]) -> Option<Vec<Combinator>> {
+    let mut combinator = Combinator {
+        sequence: Vec::new(),
+        reagent_path: Vec::new(),
+    };
+    let mut steps = Vec::with_capacity(path.len());
+
+    for name in path {
+        let reagent = reagents.iter().find(|r| &r.name == name)?;
+        combinator.add_reagent(reagent);
+        steps.push(combinator.clone());
+    }
+
+    Some(steps)
+}
+
+/// Checks that applying `path` in order produces exactly the exitus sequence.
+pub fn is_valid_path(exitus: &Reagent, reagents: &[Reagent], path: &[String]) -> bool {
+    match replay_path(reagents, path) {
+        Some(combinator) => combinator.sequence == exitus.atoms,
+        None => false,
+    }
+}
+
 fn heuristic(current: &Combinator, exitus: &Reagent, depth: usize) -> OrderedFloat<f32> {
     let mut score = 0.0;
     let mut index_c = 0;
@@ -92,14 +479,36 @@ fn heuristic(current: &Combinator, exitus: &Reagent, depth: usize) -> OrderedFlo
     OrderedFloat(score)
 }
 
+/// Number of leading exitus atoms `sequence` already has in place.
+fn matched_prefix(sequence: &[String], exitus: &Reagent) -> usize {
+    sequence
+        .iter()
+        .zip(&exitus.atoms)
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+/// Best-first search ordered by the heuristic, calling `on_progress` every
+/// [`PROGRESS_INTERVAL`] expanded nodes.
+///
+/// Every expanded node, and the one producing the exitus, is added to `tree`
+/// when one is given.
 pub fn priority_search(
     exitus: &Reagent,
-    start: &Reagent,
+    start: &Combinator,
     reagents: &[Reagent],
-) -> Option<Vec<String>> {
+    options: &SolverOptions,
+    on_progress: &mut dyn FnMut(&SearchStats),
+    mut tree: Option<&mut SearchTree>,
+) -> (Option<Vec<String>>, SearchStats) {
+    let start_time = Instant::now();
+    let mut stats = SearchStats {
+        best_match: matched_prefix(&start.sequence, exitus),
+        depth: start.reagent_path.len(),
+        ..SearchStats::default()
+    };
     let mut p_queue = PriorityQueue::new();
-    let mut i = 0;
-    let path = vec![start.name.clone()];
+    let prev_name = start.reagent_path.last().cloned().unwrap_or_default();
 
     let mut combinator = Combinator {
         sequence: Vec::new(),
@@ -107,19 +516,36 @@ pub fn priority_search(
     };
 
     p_queue.push(
-        (start.atoms.clone(), start.name.clone(), path.clone()),
+        (
+            start.sequence.clone(),
+            prev_name,
+            start.reagent_path.clone(),
+            None,
+        ),
         heuristic(&combinator, exitus, 1),
     );
 
-    while !p_queue.is_empty() && i < 2500 {
-        let ((current, prev_name, current_path), _) = match p_queue.pop() {
+    let mut path = None;
+    'search: while !p_queue.is_empty() && stats.nodes_expanded < options.max_iterations {
+        let ((current, prev_name, current_path, parent), score) = match p_queue.pop() {
             Some(x) => x,
             None => break,
         };
 
-        if current_path.len() >= MAX_DEPTH {
+        if current_path.len() >= options.max_depth {
             break;
         }
+        stats.depth = current_path.len();
+
+        let node = tree.as_deref_mut().map(|tree| {
+            tree.push(SearchNode {
+                parent,
+                reagent: prev_name.clone(),
+                sequence: current.clone(),
+                score: score.into_inner(),
+                depth: current_path.len(),
+            })
+        });
 
         for reagent in reagents {
             if reagent.name == prev_name {
@@ -128,23 +554,276 @@ pub fn priority_search(
 
             combinator.reset(&current, &current_path);
             combinator.add_reagent(reagent);
+            stats.best_match = stats
+                .best_match
+                .max(matched_prefix(&combinator.sequence, exitus));
 
             if combinator.sequence == exitus.atoms {
-                return Some(combinator.reagent_path.clone());
+                path = Some(combinator.reagent_path.clone());
+                if let Some(tree) = tree.as_deref_mut() {
+                    tree.solution = Some(
+                        tree.push(SearchNode {
+                            parent: node,
+                            reagent: reagent.name.clone(),
+                            sequence: combinator.sequence.clone(),
+                            score: heuristic(&combinator, exitus, combinator.reagent_path.len())
+                                .into_inner(),
+                            depth: combinator.reagent_path.len(),
+                        }),
+                    );
+                }
+                break 'search;
             } else {
                 let priority = heuristic(&combinator, exitus, combinator.reagent_path.len());
                 let mut new_path = current_path.clone();
                 new_path.push(reagent.name.clone());
 
                 p_queue.push(
-                    (combinator.sequence.clone(), reagent.name.clone(), new_path),
+                    (
+                        combinator.sequence.clone(),
+                        reagent.name.clone(),
+                        new_path,
+                        node,
+                    ),
                     priority,
                 );
             }
         }
 
-        i += 1;
+        stats.nodes_expanded += 1;
+        if stats.nodes_expanded.is_multiple_of(PROGRESS_INTERVAL) {
+            stats.queue_size = p_queue.len();
+            stats.elapsed = start_time.elapsed();
+            on_progress(&stats);
+        }
     }
 
-    None
+    stats.queue_size = p_queue.len();
+    stats.elapsed = start_time.elapsed();
+    (path, stats)
+}
+
+/// Level-by-level search keeping the best `beam_width` candidates of each
+/// depth, calling `on_progress` after every level.
+pub fn beam_search(
+    exitus: &Reagent,
+    start: &Combinator,
+    reagents: &[Reagent],
+    options: &SolverOptions,
+    on_progress: &mut dyn FnMut(&SearchStats),
+) -> (Option<Vec<String>>, SearchStats) {
+    let start_time = Instant::now();
+    let mut stats = SearchStats {
+        best_match: matched_prefix(&start.sequence, exitus),
+        depth: start.reagent_path.len(),
+        queue_size: 1,
+        ..SearchStats::default()
+    };
+    let mut combinator = Combinator {
+        sequence: Vec::new(),
+        reagent_path: Vec::new(),
+    };
+    let mut seen: HashSet<Vec<String>> = HashSet::new();
+    let mut beam = vec![(start.sequence.clone(), start.reagent_path.clone())];
+
+    for depth in start.reagent_path.len()..options.max_depth {
+        let mut candidates = Vec::new();
+        stats.depth = depth;
+
+        for (current, current_path) in &beam {
+            for reagent in reagents {
+                if current_path.last() == Some(&reagent.name) {
+                    continue;
+                }
+
+                combinator.reset(current, current_path);
+                combinator.add_reagent(reagent);
+                stats.best_match = stats
+                    .best_match
+                    .max(matched_prefix(&combinator.sequence, exitus));
+
+                if combinator.sequence == exitus.atoms {
+                    stats.elapsed = start_time.elapsed();
+                    return (Some(combinator.reagent_path.clone()), stats);
+                }
+
+                if seen.insert(combinator.sequence.clone()) {
+                    candidates.push((
+                        heuristic(&combinator, exitus, combinator.reagent_path.len()),
+                        combinator.sequence.clone(),
+                        combinator.reagent_path.clone(),
+                    ));
+                }
+            }
+            stats.nodes_expanded += 1;
+        }
+
+        if candidates.is_empty() {
+            break;
+        }
+
+        candidates.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+        candidates.truncate(options.beam_width);
+        beam = candidates
+            .into_iter()
+            .map(|(_, sequence, path)| (sequence, path))
+            .collect();
+
+        stats.queue_size = beam.len();
+        stats.elapsed = start_time.elapsed();
+        on_progress(&stats);
+    }
+
+    stats.elapsed = start_time.elapsed();
+    (None, stats)
+}
+
+/// Extends `start` into a path producing `exitus` using the configured strategy.
+pub fn search(
+    exitus: &Reagent,
+    start: &Reagent,
+    reagents: &[Reagent],
+    options: &SolverOptions,
+) -> Option<Vec<String>> {
+    search_from(exitus, &Combinator::from_start(start), reagents, options)
+}
+
+/// Extends a partial path into one producing `exitus` using the configured strategy.
+pub fn search_from(
+    exitus: &Reagent,
+    start: &Combinator,
+    reagents: &[Reagent],
+    options: &SolverOptions,
+) -> Option<Vec<String>> {
+    search_with_stats(exitus, start, reagents, options, &mut |_| {}, None).0
+}
+
+/// Like [`search_from`], but reports progress and returns how far the
+/// search got. Only the priority search fills in `tree`.
+pub fn search_with_stats(
+    exitus: &Reagent,
+    start: &Combinator,
+    reagents: &[Reagent],
+    options: &SolverOptions,
+    on_progress: &mut dyn FnMut(&SearchStats),
+    tree: Option<&mut SearchTree>,
+) -> (Option<Vec<String>>, SearchStats) {
+    match options.strategy {
+        Strategy::Priority => priority_search(exitus, start, reagents, options, on_progress, tree),
+        Strategy::Beam => beam_search(exitus, start, reagents, options, on_progress),
+    }
+}
+
+/// Completes a hand-picked path prefix into one producing `exitus`.
+///
+/// An empty prefix runs the whole pipeline and returns its best path.
+pub fn finish_path(
+    exitus: &Reagent,
+    reagents: &[Reagent],
+    prefix: &[String],
+    options: &SolverOptions,
+) -> Option<Vec<String>> {
+    let combinator = replay_path(reagents, prefix)?;
+
+    if combinator.sequence == exitus.atoms {
+        Some(prefix.to_vec())
+    } else if prefix.is_empty() {
+        solve(exitus, reagents, options).best_path().cloned()
+    } else {
+        search_from(exitus, &combinator, reagents, options)
+    }
+}
+
+/// Runs the whole pipeline: drops useless reagents, picks the viable starts
+/// and searches from each of them on its own thread.
+pub fn solve(exitus: &Reagent, reagents: &[Reagent], options: &SolverOptions) -> SolveReport {
+    solve_with_progress(exitus, reagents, options, |_| {})
+}
+
+/// Like [`solve`], calling `on_progress` from the search threads as every
+/// start's search moves on and once more when it finishes.
+pub fn solve_with_progress<F>(
+    exitus: &Reagent,
+    reagents: &[Reagent],
+    options: &SolverOptions,
+    on_progress: F,
+) -> SolveReport
+where
+    F: Fn(&StartProgress) + Send + Sync + 'static,
+{
+    let (filtered_reagents, useless_reagents) = filter_useless_reagents(exitus, reagents);
+    let viable_starts = get_viable_start_reagents(exitus, &filtered_reagents);
+
+    let mut report = SolveReport {
+        exitus: exitus.clone(),
+        reagents: reagents.to_vec(),
+        options: *options,
+        useless_reagents,
+        viable_starts,
+        results: Vec::new(),
+    };
+
+    let exitus = Arc::new(exitus.clone());
+    let reagents = Arc::new(reagents.to_vec());
+    let on_progress = Arc::new(on_progress);
+
+    let (sender, receiver) = channel();
+
+    for (index, start) in report.viable_starts.iter().enumerate() {
+        let start = start.clone();
+        let sender = sender.clone();
+        let exitus = Arc::clone(&exitus);
+        let reagents = Arc::clone(&reagents);
+        let on_progress = Arc::clone(&on_progress);
+        let options = *options;
+
+        thread::spawn(move || {
+            let report_stats = |stats: &SearchStats, finished: bool| {
+                on_progress(&StartProgress {
+                    index,
+                    start: start.name.clone(),
+                    stats: *stats,
+                    finished,
+                })
+            };
+
+            let mut tree = (options.record_tree && options.strategy == Strategy::Priority)
+                .then(SearchTree::default);
+            let start_time = Instant::now();
+            let (path, stats) = search_with_stats(
+                &exitus,
+                &Combinator::from_start(&start),
+                &reagents,
+                &options,
+                &mut |stats| report_stats(stats, false),
+                tree.as_mut(),
+            );
+            let elapsed = start_time.elapsed();
+            report_stats(&stats, true);
+
+            match sender.send((
+                index,
+                StartResult {
+                    start,
+                    path,
+                    elapsed,
+                    stats,
+                    tree,
+                },
+            )) {
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("Error sending path: {:?}\n", e);
+                }
+            }
+        });
+    }
+
+    drop(sender);
+
+    let mut results: Vec<(usize, StartResult)> = receiver.into_iter().collect();
+    results.sort_by_key(|(index, _)| *index);
+    report.results = results.into_iter().map(|(_, result)| result).collect();
+
+    report
 }
@@ -0,0 +1,178 @@
+// Module: theme
+use ratatui::style::Color;
+use std::fmt;
+use std::str::FromStr;
+
+/// Named colour theme, picked at runtime or in the settings file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+    /// Blue and orange instead of green and red, safe for red-green colour
+    /// blindness.
+    Deuteranopia,
+}
+
+/// Colours of the interface by what they mark.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ThemeColors {
+    /// Plain text, e.g. a neutral log message.
+    pub text: Color,
+    /// Border of the focused pane and of popups.
+    pub focus: Color,
+    /// Border of the pane being edited.
+    pub editing: Color,
+    /// Border of prompts that need an answer.
+    pub warning: Color,
+    /// Selected list entries and tabs.
+    pub highlight: Color,
+    pub ok: Color,
+    pub error: Color,
+    /// Hints and entries that are out of reach, e.g. missing files.
+    pub muted: Color,
+    pub directory: Color,
+    /// Atoms that are part of the exitus.
+    pub exitus_atom: Color,
+    /// Negative atoms, which remove an atom when applied.
+    pub negative_atom: Color,
+    /// Atoms no reagent can remove.
+    pub unremovable_atom: Color,
+    /// Sandbox atoms in the same place as in the exitus.
+    pub atom_match: Color,
+    /// Sandbox atoms where the exitus has another atom or none.
+    pub atom_mismatch: Color,
+    /// Gauge of a start still being searched.
+    pub progress: Color,
+    /// Unfilled part of the gauges.
+    pub gauge_background: Color,
+}
+
+/// Okabe-Ito colours, told apart with any kind of colour blindness.
+const SKY_BLUE: Color = Color::Rgb(86, 180, 233);
+const BLUE: Color = Color::Rgb(0, 114, 178);
+const ORANGE: Color = Color::Rgb(230, 159, 0);
+const VERMILLION: Color = Color::Rgb(213, 94, 0);
+const YELLOW: Color = Color::Rgb(240, 228, 66);
+const REDDISH_PURPLE: Color = Color::Rgb(204, 121, 167);
+
+impl Theme {
+    pub const ALL: [Theme; 4] = [
+        Theme::Dark,
+        Theme::Light,
+        Theme::HighContrast,
+        Theme::Deuteranopia,
+    ];
+
+    /// Name of the theme in the palette and the settings file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+            Theme::HighContrast => "high-contrast",
+            Theme::Deuteranopia => "deuteranopia",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&t| t == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn colors(self) -> ThemeColors {
+        match self {
+            Theme::Dark => ThemeColors {
+                text: Color::White,
+                focus: Color::Green,
+                editing: Color::Yellow,
+                warning: Color::Yellow,
+                highlight: Color::Yellow,
+                ok: Color::Green,
+                error: Color::Red,
+                muted: Color::DarkGray,
+                directory: Color::Blue,
+                exitus_atom: Color::Cyan,
+                negative_atom: Color::Red,
+                unremovable_atom: Color::Magenta,
+                atom_match: Color::Green,
+                atom_mismatch: Color::Red,
+                progress: Color::Yellow,
+                gauge_background: Color::DarkGray,
+            },
+            // Yellow and cyan wash out on a light background.
+            Theme::Light => ThemeColors {
+                text: Color::Black,
+                focus: Color::Blue,
+                editing: Color::Magenta,
+                warning: Color::Red,
+                highlight: Color::Magenta,
+                ok: Color::Green,
+                error: Color::Red,
+                muted: Color::Gray,
+                directory: Color::Blue,
+                exitus_atom: Color::Blue,
+                negative_atom: Color::Red,
+                unremovable_atom: Color::Magenta,
+                atom_match: Color::Green,
+                atom_mismatch: Color::Red,
+                progress: Color::Magenta,
+                gauge_background: Color::Gray,
+            },
+            Theme::HighContrast => ThemeColors {
+                text: Color::White,
+                focus: Color::LightGreen,
+                editing: Color::LightYellow,
+                warning: Color::LightYellow,
+                highlight: Color::LightYellow,
+                ok: Color::LightGreen,
+                error: Color::LightRed,
+                muted: Color::Gray,
+                directory: Color::LightBlue,
+                exitus_atom: Color::LightCyan,
+                negative_atom: Color::LightRed,
+                unremovable_atom: Color::LightMagenta,
+                atom_match: Color::LightGreen,
+                atom_mismatch: Color::LightRed,
+                progress: Color::LightYellow,
+                gauge_background: Color::DarkGray,
+            },
+            Theme::Deuteranopia => ThemeColors {
+                text: Color::White,
+                focus: SKY_BLUE,
+                editing: YELLOW,
+                warning: ORANGE,
+                highlight: YELLOW,
+                ok: SKY_BLUE,
+                error: VERMILLION,
+                muted: Color::DarkGray,
+                directory: BLUE,
+                exitus_atom: SKY_BLUE,
+                negative_atom: VERMILLION,
+                unremovable_atom: REDDISH_PURPLE,
+                atom_match: SKY_BLUE,
+                atom_mismatch: ORANGE,
+                progress: ORANGE,
+                gauge_background: Color::DarkGray,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let names: Vec<&str> = Theme::ALL.iter().map(|t| t.name()).collect();
+        Theme::ALL
+            .into_iter()
+            .find(|theme| theme.name() == s.to_lowercase())
+            .ok_or_else(|| format!("unknown theme '{}' (expected {})", s, names.join(", ")))
+    }
+}
@@ -0,0 +1,189 @@
+// Module: tree
+use crate::solver::{SearchNode, SearchTree};
+use std::collections::HashSet;
+
+/// Line of the search tree explorer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TreeRow {
+    /// Index of the node in the tree.
+    pub node: usize,
+    /// Levels below the start node.
+    pub level: usize,
+    pub has_children: bool,
+    pub expanded: bool,
+}
+
+/// Popup browsing the nodes a priority search recorded from one start.
+#[derive(Clone, Debug)]
+pub struct TreeExplorer {
+    /// Index of the start in the results of the solver run.
+    pub result: usize,
+    pub start: String,
+    pub tree: SearchTree,
+    pub exitus: Vec<String>,
+    /// Children of every node, in the order they were expanded.
+    children: Vec<Vec<usize>>,
+    expanded: HashSet<usize>,
+    solution_nodes: HashSet<usize>,
+    /// Nodes shown, rebuilt whenever one is expanded or collapsed.
+    pub rows: Vec<TreeRow>,
+    pub selected: usize,
+}
+
+impl TreeExplorer {
+    /// Opens the tree with the way to the solution unfolded and the solution
+    /// selected, or just the start when there is none.
+    pub fn new(result: usize, start: &str, tree: SearchTree, exitus: &[String]) -> Self {
+        let mut children = vec![Vec::new(); tree.nodes.len()];
+        for (i, node) in tree.nodes.iter().enumerate() {
+            if let Some(parent) = node.parent {
+                children[parent].push(i);
+            }
+        }
+
+        let mut explorer = Self {
+            result,
+            start: start.to_string(),
+            solution_nodes: tree.solution_nodes(),
+            tree,
+            exitus: exitus.to_vec(),
+            children,
+            expanded: HashSet::new(),
+            rows: Vec::new(),
+            selected: 0,
+        };
+
+        match explorer.tree.solution {
+            Some(solution) => explorer.select_node(solution),
+            None => {
+                explorer.expanded.insert(0);
+                explorer.rebuild_rows();
+            }
+        }
+        explorer
+    }
+
+    /// Lists the nodes under every expanded node, depth first.
+    fn rebuild_rows(&mut self) {
+        let selected = self.selected_node();
+        let mut rows = Vec::new();
+        let mut stack: Vec<(usize, usize)> = self
+            .tree
+            .nodes
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, node)| node.parent.is_none())
+            .map(|(i, _)| (i, 0))
+            .collect();
+
+        while let Some((node, level)) = stack.pop() {
+            let expanded = self.expanded.contains(&node);
+            rows.push(TreeRow {
+                node,
+                level,
+                has_children: !self.children[node].is_empty(),
+                expanded,
+            });
+            if expanded {
+                stack.extend(self.children[node].iter().rev().map(|&c| (c, level + 1)));
+            }
+        }
+
+        self.rows = rows;
+        self.selected = selected
+            .and_then(|node| self.rows.iter().position(|row| row.node == node))
+            .unwrap_or(0);
+    }
+
+    pub fn selected_node(&self) -> Option<usize> {
+        self.rows.get(self.selected).map(|row| row.node)
+    }
+
+    pub fn node(&self, index: usize) -> &SearchNode {
+        &self.tree.nodes[index]
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.rows.len().saturating_sub(1));
+    }
+
+    /// Unfolds the way to `node` and selects it.
+    pub fn select_node(&mut self, node: usize) {
+        let mut parent = self.tree.nodes[node].parent;
+        while let Some(index) = parent {
+            self.expanded.insert(index);
+            parent = self.tree.nodes[index].parent;
+        }
+        self.rebuild_rows();
+        if let Some(row) = self.rows.iter().position(|row| row.node == node) {
+            self.selected = row;
+        }
+    }
+
+    /// Shows the children of the selected node.
+    pub fn expand(&mut self) {
+        if let Some(node) = self.selected_node() {
+            if !self.children[node].is_empty() && self.expanded.insert(node) {
+                self.rebuild_rows();
+            }
+        }
+    }
+
+    /// Hides the children of the selected node, or selects its parent when
+    /// they are hidden already.
+    pub fn collapse(&mut self) {
+        let Some(node) = self.selected_node() else {
+            return;
+        };
+
+        if self.expanded.remove(&node) {
+            self.rebuild_rows();
+        } else if let Some(parent) = self.tree.nodes[node].parent {
+            self.select_node(parent);
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        match self.selected_node() {
+            Some(node) if self.expanded.contains(&node) => self.collapse(),
+            _ => self.expand(),
+        }
+    }
+
+    pub fn is_on_solution_path(&self, node: usize) -> bool {
+        self.solution_nodes.contains(&node)
+    }
+
+    /// Number of leading exitus atoms the sequence of `node` has in place.
+    pub fn matched(&self, node: usize) -> usize {
+        self.tree.nodes[node]
+            .sequence
+            .iter()
+            .zip(&self.exitus)
+            .take_while(|(a, b)| a == b)
+            .count()
+    }
+
+    /// First node on the way from the start to `node` that has fewer exitus
+    /// atoms in place than its parent, `None` when the branch never lost any.
+    pub fn divergence(&self, node: usize) -> Option<usize> {
+        let mut branch = vec![node];
+        while let Some(parent) = self.tree.nodes[branch[branch.len() - 1]].parent {
+            branch.push(parent);
+        }
+        branch.into_iter().rev().find(|&n| {
+            self.tree.nodes[n]
+                .parent
+                .is_some_and(|parent| self.matched(n) < self.matched(parent))
+        })
+    }
+
+    /// Selects where the branch of the selected node left the exitus.
+    /// Returns the node, or `None` when there is no such node.
+    pub fn jump_to_divergence(&mut self) -> Option<usize> {
+        let node = self.divergence(self.selected_node()?)?;
+        self.select_node(node);
+        Some(node)
+    }
+}
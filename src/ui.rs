@@ -1,11 +1,24 @@
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Style},
-    widgets::{Block, BorderType, Borders, Padding, Paragraph},
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{
+        Block, BorderType, Borders, Clear, Gauge, Padding, Paragraph, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Tabs, Wrap,
+    },
     Frame,
 };
+use std::path::Path;
+use std::time::Duration;
 
-use crate::app::{ActiveBlock, App, Status};
+use crate::app::{ActiveBlock, App, LayoutMode, Setting, Status, WatchMode};
+use crate::files::display_path;
+use crate::keymap::{Action, Mode};
+use crate::palette::{COMMANDS, PALETTE_ACTIONS};
+use crate::sandbox::{align, AlignedAtom, AtomMatch};
+use crate::solver::StartProgress;
+use crate::solver::{filter_useless_reagents, unremovable_atoms};
+use crate::theme::ThemeColors;
 
 /// Renders the user interface widgets.
 pub fn render(app: &mut App, frame: &mut Frame) {
@@ -13,116 +26,1141 @@ pub fn render(app: &mut App, frame: &mut Frame) {
     // See the following resources:
     // - https://docs.rs/ratatui/latest/ratatui/widgets/index.html
     // - https://github.com/ratatui-org/ratatui/tree/master/examples
-    let main_layout = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)].as_ref())
+    let colors = app.theme.colors();
+    let screen_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(1)].as_ref())
         .split(frame.size());
 
-    let reagent_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(
-            [
-                Constraint::Length(3),
-                Constraint::Min(1),
-                Constraint::Length(3),
-            ]
-            .as_ref(),
-        )
-        .split(main_layout[0]);
+    render_puzzle_tabs(app, frame, screen_layout[0]);
 
-    let file_name_input_area = reagent_layout[0];
+    let layout = pane_layout(app.layout, screen_layout[1]);
 
     let file_name_input_block = Block::default()
-        .title("Reagents file name")
+        .title(format!(
+            "Reagents file name{}{}",
+            match app.puzzle().dirty {
+                true => " [modified]",
+                false => "",
+            },
+            match app.watch {
+                WatchMode::Off => "",
+                WatchMode::Reload => " [watching]",
+                WatchMode::Solve => " [watching, solving]",
+            }
+        ))
         .borders(Borders::ALL)
         .padding(Padding::horizontal(1))
         .border_type(BorderType::Rounded)
-        .border_style(match &app.active_block {
-            ActiveBlock::FileNameInput => {
-                if app.edit_mode {
-                    Style::default().fg(Color::Yellow)
-                } else {
-                    Style::default().fg(Color::Green)
-                }
-            }
-            _ => Style::default(),
-        });
+        .border_style(border_style(app, ActiveBlock::FileNameInput));
 
-    match &app.edit_mode {
-        false => {}
+    // Scroll the file name sideways to keep the cursor in view.
+    let text_area = file_name_input_block.inner(layout.file_name_input);
+    let width = (text_area.width as usize).max(1);
+    if app.character_index < app.file_name_scroll {
+        app.file_name_scroll = app.character_index;
+    } else if app.character_index >= app.file_name_scroll + width {
+        app.file_name_scroll = app.character_index + 1 - width;
+    }
 
-        true => {
-            // Make the cursor visible and ask ratatui to put it at the specified coordinates after
-            // rendering
-            frame.set_cursor(
-                // Draw the cursor at the current position in the input field.
-                // This position is can be controlled via the left and right arrow key
-                file_name_input_area.x + app.character_index as u16 + 2,
-                // Move one line down, from the border to the input line
-                1,
-            );
-        }
+    if app.edit_mode && app.active_block == ActiveBlock::FileNameInput {
+        // Make the cursor visible and ask ratatui to put it at the cursor
+        // position in the input field after rendering.
+        frame.set_cursor(
+            text_area.x + (app.character_index - app.file_name_scroll) as u16,
+            text_area.y,
+        );
     }
 
     let file_name_input = Paragraph::new(app.file_name_input.clone())
         .alignment(Alignment::Left)
+        .scroll((0, app.file_name_scroll as u16))
         .block(file_name_input_block);
 
-    frame.render_widget(file_name_input, reagent_layout[0]);
+    frame.render_widget(file_name_input, layout.file_name_input);
 
+    let reagent_output_title = match app.mode() {
+        Mode::Editor => format!(
+            "Reagents ({})",
+            app.keymap.help(
+                Mode::Editor,
+                &[
+                    (Action::Cancel, "done"),
+                    (Action::DeleteAtom, "del atom"),
+                    (Action::DeleteLine, "del reagent"),
+                ],
+            )
+        ),
+        _ => "Reagents".to_string(),
+    };
     let reagent_output_block = Block::default()
-        .title("Reagents")
+        .title(reagent_output_title)
         .borders(Borders::ALL)
         .padding(Padding::uniform(1))
         .border_type(BorderType::Rounded)
-        .border_style(match &app.active_block {
-            ActiveBlock::ReagentOutput => {
-                if app.edit_mode {
-                    Style::default().fg(Color::Yellow)
-                } else {
-                    Style::default().fg(Color::Green)
-                }
-            }
-            _ => Style::default(),
-        });
+        .border_style(border_style(app, ActiveBlock::ReagentOutput));
 
-    let mut reagents_and_exitus = Vec::new();
-    reagents_and_exitus.push(app.exitus.clone());
-    for reagent in &app.reagents {
-        reagents_and_exitus.push(reagent.clone());
-    }
-    let reagents_and_exitus = reagents_and_exitus
-        .iter()
-        .map(|r| r.to_string())
-        .collect::<Vec<String>>()
-        .join("\n");
-    let reagents = Paragraph::new(reagents_and_exitus)
-        .alignment(Alignment::Left)
-        .block(reagent_output_block);
+    app.areas.file_name_input = layout.file_name_input;
+    app.areas.file_name_text = text_area;
+    app.areas.reagents = reagent_output_block.inner(layout.reagents);
+    app.areas.log = layout.log;
+    app.areas.solver_log = layout.solver_log;
 
-    frame.render_widget(reagents, reagent_layout[1]);
+    let reagents = match (&app.active_block, app.edit_mode) {
+        (ActiveBlock::ReagentOutput, true) => {
+            let inner = reagent_output_block.inner(layout.reagents);
+            let puzzle = app.puzzle_mut();
+            let editor = &mut puzzle.reagent_editor;
+            editor.scroll_to_cursor(inner.height as usize);
+
+            frame.set_cursor(
+                inner.x + (editor.column as u16).min(inner.width.saturating_sub(1)),
+                inner.y + (editor.row - editor.scroll) as u16,
+            );
+
+            let lines: Vec<Line> = editor
+                .lines
+                .iter()
+                .enumerate()
+                .map(|(i, line)| {
+                    let has_issue = puzzle
+                        .reagent_issues
+                        .iter()
+                        .any(|issue| issue.line == Some(i));
+                    match has_issue {
+                        true => Line::styled(line.clone(), Style::default().fg(colors.error)),
+                        false => Line::raw(line.clone()),
+                    }
+                })
+                .collect();
+
+            Paragraph::new(lines)
+                .scroll((editor.scroll as u16, 0))
+                .block(reagent_output_block)
+        }
+        _ => {
+            let lines = reagent_lines(app);
+            let height = reagent_output_block.inner(layout.reagents).height as usize;
+            let puzzle = app.puzzle_mut();
+            puzzle.reagent_scroll = puzzle
+                .reagent_scroll
+                .min(lines.len().saturating_sub(height));
+
+            Paragraph::new(lines)
+                .alignment(Alignment::Left)
+                .scroll((puzzle.reagent_scroll as u16, 0))
+                .block(reagent_output_block)
+        }
+    };
+
+    frame.render_widget(reagents, layout.reagents);
+
+    render_settings(app, frame, layout.settings);
 
     let log_block = Block::default()
         .title("Log")
         .borders(Borders::ALL)
         .padding(Padding::horizontal(1))
-        .border_type(BorderType::Rounded);
+        .border_type(BorderType::Rounded)
+        .border_style(border_style(app, ActiveBlock::Log));
 
     let log = Paragraph::new(app.log_message.clone())
         .alignment(Alignment::Left)
         .style(match &app.status {
-            Status::Error => Style::default().fg(Color::Red),
-            Status::Ok => Style::default().fg(Color::Green),
-            Status::Neutral => Style::default().fg(Color::White),
+            Status::Error => Style::default().fg(colors.error),
+            Status::Ok => Style::default().fg(colors.ok),
+            Status::Neutral => Style::default().fg(colors.text),
         })
         .block(log_block);
-    frame.render_widget(log, reagent_layout[2]);
+    frame.render_widget(log, layout.log);
+
+    match app.puzzle().sandbox {
+        Some(_) => render_sandbox(app, frame, layout.solver_log),
+        None => render_solver_log(app, frame, layout.solver_log),
+    }
+
+    if let Some(area) = layout.details {
+        render_details(app, frame, area);
+    }
+
+    if app.file_browser.is_some() {
+        render_file_browser(app, frame);
+    }
+
+    if app.recent.is_some() {
+        render_recent(app, frame);
+    }
+
+    if app.search_tree.is_some() {
+        render_search_tree(app, frame);
+    }
+
+    if app.palette.is_some() {
+        render_palette(app, frame);
+    }
+
+    if let Some(mode) = app.help {
+        render_help(app, mode, frame);
+    }
+
+    if app.confirm_quit {
+        let help = app.keymap.help(
+            Mode::ConfirmQuit,
+            &[(Action::Quit, "quit"), (Action::Save, "save and quit")],
+        );
+        render_confirm(app, "Quit anyway?", &help, frame);
+    } else if app.confirm_load {
+        let help = app.keymap.help(
+            Mode::ConfirmLoad,
+            &[(Action::Load, "reload"), (Action::Save, "save and reload")],
+        );
+        render_confirm(app, "Reload anyway?", &help, frame);
+    }
+}
+
+/// Unsaved changes prompt asking `question`, with `help` listing the keys
+/// that answer it.
+fn render_confirm(app: &App, question: &str, help: &str, frame: &mut Frame) {
+    let area = centered_rect(50, 5, frame.size());
+    let popup = Paragraph::new(format!("{} ({}, any other key - cancel)", question, help))
+        .wrap(Wrap { trim: true })
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .title("Unsaved changes")
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(app.theme.colors().warning)),
+        );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// Where the panes go in one of the layouts.
+struct PaneLayout {
+    file_name_input: Rect,
+    reagents: Rect,
+    settings: Rect,
+    log: Rect,
+    solver_log: Rect,
+    details: Option<Rect>,
+}
+
+/// Splits `area` into the panes of `mode`.
+///
+/// Stacked puts the settings and log side by side to leave the reagents as
+/// much height as possible above the solver log.
+fn pane_layout(mode: LayoutMode, area: Rect) -> PaneLayout {
+    let settings_height = Setting::ALL.len() as u16 + 2;
 
+    if mode == LayoutMode::Stacked {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Min(3),
+                Constraint::Length(settings_height),
+                Constraint::Percentage(40),
+            ])
+            .split(area);
+        let bottom = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(rows[2]);
+
+        return PaneLayout {
+            file_name_input: rows[0],
+            reagents: rows[1],
+            settings: bottom[0],
+            log: bottom[1],
+            solver_log: rows[3],
+            details: None,
+        };
+    }
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(match mode {
+            LayoutMode::Wide => vec![
+                Constraint::Percentage(30),
+                Constraint::Percentage(45),
+                Constraint::Percentage(25),
+            ],
+            _ => vec![Constraint::Percentage(35), Constraint::Percentage(65)],
+        })
+        .split(area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(1),
+            Constraint::Length(settings_height),
+            Constraint::Length(3),
+        ])
+        .split(columns[0]);
+
+    PaneLayout {
+        file_name_input: rows[0],
+        reagents: rows[1],
+        settings: rows[2],
+        log: rows[3],
+        solver_log: columns[1],
+        details: columns.get(2).copied(),
+    }
+}
+
+/// Renders a tab per open puzzle along the top of the screen.
+fn render_puzzle_tabs(app: &mut App, frame: &mut Frame, area: Rect) {
+    let colors = app.theme.colors();
+    app.areas.puzzle_tabs = area;
+
+    let titles: Vec<String> = app.puzzles.iter().map(|p| p.title()).collect();
+    let tabs = Tabs::new(titles).select(app.active_puzzle).highlight_style(
+        Style::default()
+            .fg(colors.highlight)
+            .add_modifier(Modifier::BOLD),
+    );
+    frame.render_widget(tabs, area);
+}
+
+/// Renders the solver log with its overview and solution tabs.
+fn render_solver_log(app: &mut App, frame: &mut Frame, area: Rect) {
+    let colors = app.theme.colors();
     let solution_output_block = Block::default()
-        .title("Solver log (enter - edit mode toggle, q - quit, s - solve, r - read file)")
+        .title(format!(
+            "Solver log ({})",
+            app.keymap.help(
+                Mode::Normal,
+                &[
+                    (Action::FocusNext, "focus"),
+                    (Action::Confirm, "edit"),
+                    (Action::Quit, "quit"),
+                    (Action::Solve, "solve"),
+                    (Action::Load, "read file"),
+                    (Action::Browse, "browse"),
+                    (Action::Save, "save"),
+                    (Action::SaveAs, "save as"),
+                    (Action::ToggleSandbox, "sandbox"),
+                    (Action::PreviousTab, "previous tab"),
+                    (Action::NextTab, "next tab"),
+                    (Action::PageUp, "scroll up"),
+                    (Action::PageDown, "scroll down"),
+                ],
+            )
+        ))
+        .padding(Padding::uniform(1))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(border_style(app, ActiveBlock::SolverLog));
+    let solution_output_area = solution_output_block.inner(area);
+    frame.render_widget(solution_output_block, area);
+
+    let solution_output_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(1)].as_ref())
+        .split(solution_output_area);
+    app.areas.solver_tabs = solution_output_layout[0];
+    app.areas.solver_content = solution_output_layout[1];
+
+    let tabs = Tabs::new(app.solver_tab_titles())
+        .select(app.puzzle().solver_tab)
+        .highlight_style(Style::default().fg(colors.highlight))
+        .block(Block::default().borders(Borders::BOTTOM));
+    frame.render_widget(tabs, solution_output_layout[0]);
+
+    if app
+        .puzzle()
+        .search
+        .as_ref()
+        .is_some_and(|search| search.finishing.is_none())
+    {
+        render_search_progress(app, frame, solution_output_layout[1]);
+        return;
+    }
+
+    // Clamp the scroll offset here, where the height of the pane is known.
+    let lines = app.solver_tab_lines();
+    let height = solution_output_layout[1].height as usize;
+    let max_scroll = lines.len().saturating_sub(height);
+    let puzzle = app.puzzle_mut();
+    puzzle.solver_scroll = puzzle.solver_scroll.min(max_scroll);
+    let scroll = puzzle.solver_scroll;
+
+    let solution_output = Paragraph::new(lines.join("\n")).scroll((scroll as u16, 0));
+    frame.render_widget(solution_output, solution_output_layout[1]);
+
+    if max_scroll > 0 {
+        let mut scrollbar_state = ScrollbarState::new(max_scroll).position(scroll);
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight),
+            area.inner(&Margin {
+                vertical: 1,
+                horizontal: 0,
+            }),
+            &mut scrollbar_state,
+        );
+    }
+}
+
+/// Border style of a pane, in the focus or editing colour of the theme.
+fn border_style(app: &App, block: ActiveBlock) -> Style {
+    let colors = app.theme.colors();
+    match (app.active_block == block, app.edit_mode) {
+        (true, true) => Style::default().fg(colors.editing),
+        (true, false) => Style::default().fg(colors.focus),
+        (false, _) => Style::default(),
+    }
+}
+
+/// Renders the solver options, one per line, marking the selected one while
+/// the pane has focus.
+fn render_settings(app: &mut App, frame: &mut Frame, area: Rect) {
+    let colors = app.theme.colors();
+    let focused = app.active_block == ActiveBlock::Settings;
+    let block = Block::default()
+        .title(match focused {
+            true => format!(
+                "Settings ({})",
+                app.keymap.help(
+                    Mode::Normal,
+                    &[(Action::Left, "less"), (Action::Right, "more")],
+                )
+            ),
+            false => "Settings".to_string(),
+        })
+        .borders(Borders::ALL)
+        .padding(Padding::horizontal(1))
+        .border_type(BorderType::Rounded)
+        .border_style(border_style(app, ActiveBlock::Settings));
+    app.areas.settings = block.inner(area);
+
+    let label_width = Setting::ALL
+        .iter()
+        .map(|s| s.label().len())
+        .max()
+        .unwrap_or(0);
+    let lines: Vec<Line> = Setting::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, setting)| {
+            let text = format!(
+                "{:<label_width$}  {}",
+                setting.label(),
+                setting.value(&app.solver_options)
+            );
+            match focused && i == app.selected_setting {
+                true => Line::styled(format!("> {}", text), Style::default().fg(colors.highlight)),
+                false => Line::raw(text),
+            }
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// Renders the details pane of the wide layout: a summary of the puzzle,
+/// the selected reagent and the last solver run.
+fn render_details(app: &App, frame: &mut Frame, area: Rect) {
+    let colors = app.theme.colors();
+    let puzzle = app.puzzle();
+    let heading = |text: &str| {
+        Line::styled(
+            text.to_string(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )
+    };
+    let row = |label: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{:<12}", label), Style::default().fg(colors.muted)),
+            Span::raw(value),
+        ])
+    };
+
+    let mut unremovable: Vec<String> = unremovable_atoms(&puzzle.exitus, &puzzle.reagents)
+        .into_iter()
+        .collect();
+    unremovable.sort();
+    let (_, useless) = filter_useless_reagents(&puzzle.exitus, &puzzle.reagents);
+
+    let mut lines = vec![
+        heading("Puzzle"),
+        row(
+            "File",
+            puzzle.loaded_file.clone().unwrap_or_else(|| puzzle.title()),
+        ),
+        row("Exitus", format!("{} atoms", puzzle.exitus.atoms.len())),
+        row(
+            "Reagents",
+            format!("{} ({} useless)", puzzle.reagents.len(), useless.len()),
+        ),
+        row(
+            "Unremovable",
+            match unremovable.is_empty() {
+                true => "none".to_string(),
+                false => unremovable.join(" "),
+            },
+        ),
+    ];
+
+    if let Some(reagent) = puzzle.reagents.get(puzzle.selected_reagent) {
+        let in_exitus = reagent
+            .atoms
+            .iter()
+            .filter(|atom| puzzle.exitus.atoms.contains(atom))
+            .count();
+        lines.extend([
+            Line::raw(""),
+            heading("Selected reagent"),
+            row("Name", reagent.name.clone()),
+            row("Atoms", reagent.atoms.join(" ")),
+            row(
+                "In exitus",
+                format!("{} of {} atoms", in_exitus, reagent.atoms.len()),
+            ),
+        ]);
+        if useless.contains(reagent) {
+            lines.push(Line::styled(
+                "Dropped by the solver as useless",
+                Style::default().fg(colors.muted),
+            ));
+        }
+    }
+
+    if let Some(report) = &puzzle.solve_report {
+        let nodes: usize = report.results.iter().map(|r| r.stats.nodes_expanded).sum();
+        let elapsed: Duration = report.results.iter().map(|r| r.elapsed).sum();
+        lines.extend([
+            Line::raw(""),
+            heading("Last solve"),
+            row(
+                "Solutions",
+                format!(
+                    "{} of {} starts",
+                    report.solutions().count(),
+                    report.viable_starts.len()
+                ),
+            ),
+            row(
+                "Shortest",
+                report.best_path().map_or("none".to_string(), |path| {
+                    format!("{} reagents", path.len())
+                }),
+            ),
+            row("Nodes", nodes.to_string()),
+            row("Time", format!("{} ms", elapsed.as_millis())),
+        ]);
+    }
+
+    let block = Block::default()
+        .title("Details")
+        .borders(Borders::ALL)
+        .padding(Padding::horizontal(1))
+        .border_type(BorderType::Rounded);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(block),
+        area,
+    );
+}
+
+/// Renders a gauge per viable start while the solver runs, labelled with
+/// its search statistics.
+fn render_search_progress(app: &App, frame: &mut Frame, area: Rect) {
+    let colors = app.theme.colors();
+    let Some(search) = &app.puzzle().search else {
+        return;
+    };
+
+    let starts: Vec<&StartProgress> = search.progress.iter().flatten().collect();
+    let mut constraints = vec![Constraint::Length(2)];
+    constraints.extend(starts.iter().map(|_| Constraint::Length(1)));
+    constraints.push(Constraint::Min(0));
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(area);
+
+    let finished = starts.iter().filter(|p| p.finished).count();
+    frame.render_widget(
+        Paragraph::new(format!(
+            "Searching... {:.1}s, {} of {} starts done",
+            search.started.elapsed().as_secs_f64(),
+            finished,
+            starts.len()
+        )),
+        rows[0],
+    );
+
+    let name_width = starts.iter().map(|p| p.start.len()).max().unwrap_or(0);
+    for (progress, row) in starts.iter().zip(rows.iter().skip(1)) {
+        let stats = &progress.stats;
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(name_width as u16 + 2),
+                Constraint::Min(1),
+            ])
+            .split(*row);
+
+        let ratio = match progress.finished {
+            true => 1.0,
+            false => stats.progress(&search.options),
+        };
+        let color = match progress.finished {
+            true => colors.ok,
+            false => colors.progress,
+        };
+        let label = format!(
+            "{} nodes, queue {}, best {}/{}, {} ms",
+            stats.nodes_expanded,
+            stats.queue_size,
+            stats.best_match,
+            app.puzzle().exitus.atoms.len(),
+            stats.elapsed.as_millis()
+        );
+
+        frame.render_widget(Paragraph::new(progress.start.clone()), columns[0]);
+        frame.render_widget(
+            Gauge::default()
+                .ratio(ratio)
+                .label(label)
+                .gauge_style(Style::default().fg(color).bg(colors.gauge_background)),
+            columns[1],
+        );
+    }
+}
+
+/// Lists the exitus and reagents, marking the selected reagent while the
+/// sandbox is open or the reagent pane has focus.
+///
+/// Exitus atoms, negatives and atoms nothing can remove (underlined) get
+/// their own theme colours. Reagents the solver drops as useless are dimmed.
+fn reagent_lines(app: &App) -> Vec<Line<'static>> {
+    let colors = app.theme.colors();
+    let puzzle = app.puzzle();
+    let selected = match (&puzzle.sandbox, &app.active_block) {
+        (Some(_), _) | (None, ActiveBlock::ReagentOutput) => Some(puzzle.selected_reagent + 1),
+        _ => None,
+    };
+    let unremovable = unremovable_atoms(&puzzle.exitus, &puzzle.reagents);
+    let (_, useless) = filter_useless_reagents(&puzzle.exitus, &puzzle.reagents);
+
+    std::iter::once(&puzzle.exitus)
+        .chain(puzzle.reagents.iter())
+        .enumerate()
+        .flat_map(|(i, reagent)| {
+            let mut spans = Vec::new();
+            if selected == Some(i) {
+                spans.push(Span::styled("> ", Style::default().fg(colors.highlight)));
+            }
+
+            spans.push(Span::styled(
+                reagent.name.clone(),
+                match selected == Some(i) {
+                    true => Style::default().fg(colors.highlight),
+                    false => Style::default().add_modifier(Modifier::BOLD),
+                },
+            ));
+
+            for atom in &reagent.atoms {
+                let style = if atom.starts_with('-') {
+                    Style::default().fg(colors.negative_atom)
+                } else if puzzle.exitus.atoms.contains(atom) {
+                    Style::default().fg(colors.exitus_atom)
+                } else if unremovable.contains(atom) {
+                    Style::default()
+                        .fg(colors.unremovable_atom)
+                        .add_modifier(Modifier::UNDERLINED)
+                } else {
+                    Style::default()
+                };
+                spans.push(Span::raw(" "));
+                spans.push(Span::styled(atom.clone(), style));
+            }
+
+            let mut line = Line::from(spans);
+            if useless.contains(reagent) {
+                line = line.patch_style(Style::default().add_modifier(Modifier::DIM));
+            }
+            [line, Line::raw("")]
+        })
+        .collect()
+}
+
+/// Renders the sandbox: the path so far and its sequence aligned against the exitus.
+fn render_sandbox(app: &mut App, frame: &mut Frame, area: Rect) {
+    let colors = app.theme.colors();
+    let puzzle = app.puzzle();
+    let Some(sandbox) = &puzzle.sandbox else {
+        return;
+    };
+
+    let block = Block::default()
+        .title(format!(
+            "Sandbox ({})",
+            app.keymap.help(
+                Mode::Sandbox,
+                &[
+                    (Action::Confirm, "apply"),
+                    (Action::Undo, "undo"),
+                    (Action::Finish, "finish with solver"),
+                    (Action::Cancel, "close"),
+                ],
+            )
+        ))
         .padding(Padding::uniform(1))
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded);
-    let solution_output = Paragraph::new(app.solver_log.join("\n")).block(solution_output_block);
-    frame.render_widget(solution_output, main_layout[1])
+
+    let current = sandbox.current();
+    let alignment = sandbox.alignment(&puzzle.exitus);
+    let matched = alignment
+        .iter()
+        .take_while(|a| a.status == AtomMatch::Match)
+        .count();
+
+    let [sequence_line, exitus_line] = alignment_lines(&alignment, &colors);
+
+    let mut lines = vec![
+        Line::raw(match current.reagent_path.is_empty() {
+            true => "Path: pick a reagent and press enter to apply it".to_string(),
+            false => format!("Path: {}", current.reagent_path.join(" -> ")),
+        }),
+        Line::raw(""),
+        sequence_line,
+        exitus_line,
+        Line::raw(""),
+        Line::raw(format!(
+            "{} of {} exitus atoms in place",
+            matched,
+            puzzle.exitus.atoms.len()
+        )),
+    ];
+
+    if puzzle
+        .search
+        .as_ref()
+        .is_some_and(|search| search.finishing.as_ref() == Some(&current.reagent_path))
+    {
+        lines.push(Line::raw(""));
+        lines.push(Line::styled(
+            "Finishing the path with the solver...",
+            Style::default().fg(colors.progress),
+        ));
+    }
+
+    match &sandbox.finished {
+        Some(Some(path)) => {
+            lines.push(Line::raw(""));
+            lines.push(Line::styled(
+                format!("Finished by the solver: {}", path.join(" -> ")),
+                Style::default().fg(colors.ok),
+            ));
+        }
+        Some(None) => {
+            lines.push(Line::raw(""));
+            lines.push(Line::styled(
+                "The solver found no way to finish this path",
+                Style::default().fg(colors.error),
+            ));
+        }
+        None => {}
+    }
+
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// A sequence line above an exitus line, atoms in the same place in the
+/// same column and coloured by whether they match.
+fn alignment_lines(alignment: &[AlignedAtom], colors: &ThemeColors) -> [Line<'static>; 2] {
+    let mut sequence_spans = vec![Span::raw("Sequence  ")];
+    let mut exitus_spans = vec![Span::raw("Exitus    ")];
+    for atom in alignment {
+        let width = atom
+            .sequence
+            .iter()
+            .chain(atom.exitus.iter())
+            .map(|a| a.len())
+            .max()
+            .unwrap_or(0);
+        let (sequence_style, exitus_style) = match atom.status {
+            AtomMatch::Match => (
+                Style::default().fg(colors.atom_match),
+                Style::default().fg(colors.atom_match),
+            ),
+            AtomMatch::Mismatch | AtomMatch::Extra => {
+                (Style::default().fg(colors.atom_mismatch), Style::default())
+            }
+            AtomMatch::Missing => (Style::default(), Style::default().fg(colors.muted)),
+        };
+        sequence_spans.push(Span::styled(
+            format!("{:<width$} ", atom.sequence.clone().unwrap_or_default()),
+            sequence_style,
+        ));
+        exitus_spans.push(Span::styled(
+            format!("{:<width$} ", atom.exitus.clone().unwrap_or_default()),
+            exitus_style,
+        ));
+    }
+    [Line::from(sequence_spans), Line::from(exitus_spans)]
+}
+
+/// Renders the keys of `mode` in a popup, plus the commands while the
+/// palette is open.
+fn render_help(app: &App, mode: Mode, frame: &mut Frame) {
+    let colors = app.theme.colors();
+    let bindings = app.keymap.mode_bindings(mode);
+    let keys: Vec<String> = bindings
+        .iter()
+        .map(|(_, keys)| {
+            keys.iter()
+                .map(|k| k.to_string())
+                .collect::<Vec<String>>()
+                .join("/")
+        })
+        .collect();
+    let key_width = keys.iter().map(|k| k.chars().count()).max().unwrap_or(0);
+
+    let mut lines: Vec<Line> = bindings
+        .iter()
+        .zip(&keys)
+        .map(|((action, _), keys)| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<key_width$}  ", keys),
+                    Style::default().fg(colors.highlight),
+                ),
+                Span::raw(action.description()),
+            ])
+        })
+        .collect();
+
+    if let Mode::Normal | Mode::Palette = mode {
+        lines.push(Line::raw(""));
+        lines.push(Line::styled(
+            "Commands",
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+        let actions: Vec<&str> = PALETTE_ACTIONS.iter().map(|a| a.name()).collect();
+        lines.push(Line::raw(actions.join(", ")));
+        for (_, usage) in COMMANDS {
+            lines.push(Line::raw(usage));
+        }
+    }
+
+    let area = centered_rect(60, lines.len() as u16 + 4, frame.size());
+    let popup = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .title(format!("Keys: {} (any key - close)", mode.name()))
+            .padding(Padding::uniform(1))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(colors.focus)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// Renders the file browser popup, previewing the exitus of the selected
+/// puzzle below the listing.
+fn render_file_browser(app: &App, frame: &mut Frame) {
+    let colors = app.theme.colors();
+    let Some(browser) = &app.file_browser else {
+        return;
+    };
+
+    let size = frame.size();
+    let area = centered_rect(60, size.height.saturating_sub(4).max(8), size);
+    let block = Block::default()
+        .title(format!(
+            "Open {} ({})",
+            browser.dir.display(),
+            app.keymap.help(
+                Mode::Browser,
+                &[
+                    (Action::Confirm, "open"),
+                    (Action::Left, "parent"),
+                    (Action::Cancel, "close"),
+                ],
+            )
+        ))
+        .padding(Padding::horizontal(1))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(colors.focus));
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(2)])
+        .split(inner);
+
+    // Keep the selected entry in view.
+    let height = layout[0].height as usize;
+    let scroll = (browser.selected + 1).saturating_sub(height);
+
+    let lines: Vec<Line> = browser
+        .entries
+        .iter()
+        .enumerate()
+        .skip(scroll)
+        .take(height)
+        .map(|(i, entry)| {
+            let (name, style) = match entry.is_dir {
+                true => (
+                    format!("{}/", entry.name),
+                    Style::default().fg(colors.directory),
+                ),
+                false => (entry.name.clone(), Style::default()),
+            };
+            match i == browser.selected {
+                true => Line::styled(format!("> {}", name), Style::default().fg(colors.highlight)),
+                false => Line::styled(format!("  {}", name), style),
+            }
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), layout[0]);
+
+    let preview = match &browser.preview {
+        Some(Ok(exitus)) => Line::styled(exitus.clone(), Style::default().fg(colors.exitus_atom)),
+        Some(Err(e)) => Line::styled(e.clone(), Style::default().fg(colors.error)),
+        None => Line::raw(""),
+    };
+    frame.render_widget(
+        Paragraph::new(preview).block(Block::default().borders(Borders::TOP)),
+        layout[1],
+    );
+}
+
+/// Renders the recent files popup, most recent first. Files that no
+/// longer exist are muted.
+fn render_recent(app: &App, frame: &mut Frame) {
+    let colors = app.theme.colors();
+    let Some(selected) = app.recent else {
+        return;
+    };
+
+    let files = &app.settings.recent_files;
+    let area = centered_rect(60, files.len() as u16 + 4, frame.size());
+    let lines: Vec<Line> = files
+        .iter()
+        .enumerate()
+        .map(|(i, file)| {
+            let path = Path::new(file);
+            let name = display_path(path);
+            match (i == selected, path.is_file()) {
+                (true, _) => {
+                    Line::styled(format!("> {}", name), Style::default().fg(colors.highlight))
+                }
+                (false, true) => Line::raw(format!("  {}", name)),
+                (false, false) => {
+                    Line::styled(format!("  {}", name), Style::default().fg(colors.muted))
+                }
+            }
+        })
+        .collect();
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .title(format!(
+                "Recent files ({})",
+                app.keymap.help(
+                    Mode::Recent,
+                    &[(Action::Confirm, "open"), (Action::Cancel, "close")],
+                )
+            ))
+            .padding(Padding::uniform(1))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(colors.focus)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// Renders the search tree explorer popup, one node per line with the way
+/// to the solution highlighted, and the selected node against the exitus
+/// below.
+fn render_search_tree(app: &App, frame: &mut Frame) {
+    let colors = app.theme.colors();
+    let Some(explorer) = &app.search_tree else {
+        return;
+    };
+
+    let size = frame.size();
+    let area = centered_rect(80, size.height.saturating_sub(4).max(8), size);
+    let block = Block::default()
+        .title(format!(
+            "Search tree from {}, {} nodes ({})",
+            explorer.start,
+            explorer.tree.nodes.len(),
+            app.keymap.help(
+                Mode::Tree,
+                &[
+                    (Action::Confirm, "fold"),
+                    (Action::Diverge, "divergence"),
+                    (Action::NextTab, "next start"),
+                    (Action::Cancel, "close"),
+                ],
+            )
+        ))
+        .padding(Padding::horizontal(1))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(colors.focus));
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(4)])
+        .split(inner);
+
+    // Keep the selected node in view.
+    let height = layout[0].height as usize;
+    let scroll = (explorer.selected + 1).saturating_sub(height);
+
+    let lines: Vec<Line> = explorer
+        .rows
+        .iter()
+        .enumerate()
+        .skip(scroll)
+        .take(height)
+        .map(|(i, row)| {
+            let node = explorer.node(row.node);
+            let fold = match (row.has_children, row.expanded) {
+                (false, _) => " ",
+                (true, false) => "\u{25B8}",
+                (true, true) => "\u{25BE}",
+            };
+            let text = format!(
+                "{}{} {}  {:.2}",
+                "  ".repeat(row.level),
+                fold,
+                node.reagent,
+                node.score
+            );
+            let style = match (
+                i == explorer.selected,
+                explorer.is_on_solution_path(row.node),
+            ) {
+                (true, _) => Style::default().fg(colors.highlight),
+                (false, true) => Style::default().fg(colors.ok),
+                (false, false) => Style::default(),
+            };
+            let marker = match i == explorer.selected {
+                true => "> ",
+                false => "  ",
+            };
+            Line::from(vec![
+                Span::styled(format!("{}{}", marker, text), style),
+                Span::styled(
+                    format!("  {}", node.sequence.join(" ")),
+                    Style::default().fg(colors.muted),
+                ),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), layout[0]);
+
+    let details = match explorer.selected_node() {
+        Some(index) => {
+            let node = explorer.node(index);
+            let [sequence_line, exitus_line] =
+                alignment_lines(&align(&node.sequence, &explorer.exitus), &colors);
+            let mut summary = format!(
+                "{}, depth {}, score {:.2}, {} of {} exitus atoms in place",
+                node.reagent,
+                node.depth,
+                node.score,
+                explorer.matched(index),
+                explorer.exitus.len()
+            );
+            if explorer.tree.solution == Some(index) {
+                summary.push_str(", solved");
+            }
+            vec![Line::raw(summary), sequence_line, exitus_line]
+        }
+        None => Vec::new(),
+    };
+    frame.render_widget(
+        Paragraph::new(details).block(Block::default().borders(Borders::TOP)),
+        layout[1],
+    );
+}
+
+/// Renders the command palette along the bottom of the screen, with the
+/// completions offered when there is more than one.
+fn render_palette(app: &App, frame: &mut Frame) {
+    let colors = app.theme.colors();
+    let Some(palette) = &app.palette else {
+        return;
+    };
+
+    let size = frame.size();
+    let height = match palette.candidates.is_empty() {
+        true => 3,
+        false => 4,
+    }
+    .min(size.height);
+    let area = Rect::new(size.x, size.bottom() - height, size.width, height);
+
+    let mut lines = vec![Line::raw(format!(":{}", palette.input))];
+    if !palette.candidates.is_empty() {
+        lines.push(Line::styled(
+            palette.candidates.join("  "),
+            Style::default().fg(colors.muted),
+        ));
+    }
+
+    let block = Block::default()
+        .title(format!(
+            "Command ({})",
+            app.keymap.help(
+                Mode::Palette,
+                &[
+                    (Action::Confirm, "run"),
+                    (Action::Complete, "complete"),
+                    (Action::Cancel, "cancel"),
+                ],
+            )
+        ))
+        .padding(Padding::horizontal(1))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(colors.editing));
+    let inner = block.inner(area);
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+    frame.set_cursor(
+        (inner.x + palette.input.chars().count() as u16 + 1).min(inner.right().saturating_sub(1)),
+        inner.y,
+    );
+}
+
+/// Returns a rectangle of `width` percent and `height` rows centered in `area`.
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(height),
+            Constraint::Min(0),
+        ])
+        .split(area);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - width) / 2),
+            Constraint::Percentage(width),
+            Constraint::Percentage((100 - width) / 2),
+        ])
+        .split(vertical[1])[1]
 }